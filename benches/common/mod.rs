@@ -0,0 +1,16 @@
+//! Timing helper shared by the benches.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// Returns the best of a few runs, to reduce noise.
+pub fn time<T>(f: impl Fn() -> T) -> Duration {
+    (0..5)
+        .map(|_| {
+            let start = Instant::now();
+            black_box(f());
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
@@ -0,0 +1,30 @@
+//! Decoding throughput on large inputs.
+//!
+//! Run with `cargo bench --bench decode`.
+
+mod common;
+
+use std::time::Duration;
+
+use common::time;
+use evaporate::text_removal::{create_secret, extract_partial, extract_secret};
+
+fn main() {
+    for scale in [1, 4, 16] {
+        let carrier = "The quick brown fox jumps over the lazy dog. ".repeat(1_000 * scale);
+        let secret = "секрет 🦀 ".repeat(10_000 * scale);
+        let message = create_secret(&carrier, &secret);
+
+        let full = time(|| extract_secret(&message));
+        let partial = time(|| extract_partial(&message));
+        let mib_per_s = |d: Duration| message.len() as f64 / d.as_secs_f64() / (1024.0 * 1024.0);
+        println!(
+            "message {:>9} B: extract_secret {:>10?} ({:>6.0} MiB/s), extract_partial {:>10?} ({:>6.0} MiB/s)",
+            message.len(),
+            full,
+            mib_per_s(full),
+            partial,
+            mib_per_s(partial)
+        );
+    }
+}
@@ -0,0 +1,29 @@
+//! Byte decoding with the lookup table against the loop it replaced.
+//!
+//! Run with `cargo bench --bench decode_byte --features bench-internals`.
+
+mod common;
+
+use std::time::Duration;
+
+use common::time;
+use evaporate::text_removal::{decode_bytes_loop, decode_bytes_table, encode_only};
+
+fn main() {
+    for scale in [1, 4, 16] {
+        let payload = encode_only(&"секрет 🦀 ".repeat(10_000 * scale));
+        assert_eq!(decode_bytes_loop(&payload), decode_bytes_table(&payload));
+
+        let looped = time(|| decode_bytes_loop(&payload));
+        let table = time(|| decode_bytes_table(&payload));
+        let mib_per_s = |d: Duration| payload.len() as f64 / d.as_secs_f64() / (1024.0 * 1024.0);
+        println!(
+            "payload {:>9} B: loop {:>10?} ({:>6.0} MiB/s), table {:>10?} ({:>6.0} MiB/s)",
+            payload.len(),
+            looped,
+            mib_per_s(looped),
+            table,
+            mib_per_s(table)
+        );
+    }
+}
@@ -0,0 +1,34 @@
+//! Timing check that spread insertion scales linearly with input size.
+//!
+//! Run with `cargo bench --bench spread`.
+
+mod common;
+
+use std::time::Duration;
+
+use common::time;
+use evaporate::text_removal::{create_secret, create_secret_spread, SpreadDensity};
+
+fn main() {
+    let mut previous: Option<Duration> = None;
+    for scale in [1, 2, 4, 8] {
+        let carrier = "The quick brown fox jumps over the lazy dog. ".repeat(10_000 * scale);
+        let secret = "секрет 🦀 ".repeat(1_000 * scale);
+
+        let middle = time(|| create_secret(&carrier, &secret));
+        let even = time(|| create_secret_spread(&carrier, &secret, SpreadDensity::Even));
+        let gap = time(|| create_secret_spread(&carrier, &secret, SpreadDensity::FixedGap(3)));
+
+        let growth = previous.map(|p| even.as_secs_f64() / p.as_secs_f64());
+        previous = Some(even);
+        println!(
+            "carrier {:>8} B, secret {:>7} B: middle {:>10?}, even {:>10?}, gap(3) {:>10?}, even growth {}",
+            carrier.len(),
+            secret.len(),
+            middle,
+            even,
+            gap,
+            growth.map_or("-".to_string(), |g| format!("x{g:.2}"))
+        );
+    }
+}
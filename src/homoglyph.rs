@@ -0,0 +1,171 @@
+//! Fallback encoding for channels that strip invisible characters.
+//!
+//! Instead of adding characters, the secret is carried by the visible text
+//! itself: every letter that has a look-alike in another script carries one bit,
+//! Latin for `0` and Cyrillic for `1` (e.g. Latin `a` vs Cyrillic `а`).
+//!
+//! **Warning:** this changes the code points of the visible text. The message
+//! looks the same, but searching, spell checking or comparing it against the
+//! original will show the difference, and Cyrillic carriers get Latin letters
+//! mixed in.
+
+/// Latin letters and their Cyrillic look-alikes.
+const CONFUSABLES: [(char, char); 18] = [
+    ('a', 'а'),
+    ('c', 'с'),
+    ('e', 'е'),
+    ('o', 'о'),
+    ('p', 'р'),
+    ('x', 'х'),
+    ('y', 'у'),
+    ('A', 'А'),
+    ('B', 'В'),
+    ('C', 'С'),
+    ('E', 'Е'),
+    ('H', 'Н'),
+    ('K', 'К'),
+    ('M', 'М'),
+    ('O', 'О'),
+    ('P', 'Р'),
+    ('T', 'Т'),
+    ('X', 'Х'),
+];
+
+/// Returns the confusable pair `c` belongs to and the bit it encodes.
+fn lookup(c: char) -> Option<((char, char), bool)> {
+    CONFUSABLES
+        .iter()
+        .find(|&&(latin, cyrillic)| c == latin || c == cyrillic)
+        .map(|&pair| (pair, c == pair.1))
+}
+
+/// Returns how many confusable letters of `visible` can carry a bit.
+fn positions(visible: &str) -> usize {
+    visible.chars().filter(|&c| lookup(c).is_some()).count()
+}
+
+/// Returns how many bytes of secret `visible` can carry. A carrier with fewer
+/// than eight confusable letters has no room for the terminator, so it can't
+/// carry even an empty secret.
+pub fn capacity(visible: &str) -> usize {
+    // One byte is reserved for the terminator.
+    (positions(visible) / 8).saturating_sub(1)
+}
+
+/// Encodes `secret` by swapping confusable letters of `visible`.
+///
+/// The secret is followed by a NUL terminator, so it must not contain NUL
+/// itself. Returns `None` if it does or if `visible` has too few confusable
+/// letters (see `capacity`).
+pub fn create_secret(visible: &str, secret: &str) -> Option<String> {
+    // The secret and its terminator need 8 positions per byte.
+    if secret.contains('\0') || secret.len() >= positions(visible) / 8 {
+        return None;
+    }
+
+    let mut bits = secret
+        .bytes()
+        .chain(std::iter::once(0))
+        .flat_map(|byte| (0..8).map(move |i| (byte >> i) & 1 == 1));
+    let result = visible
+        .chars()
+        .map(|c| match lookup(c) {
+            Some(((latin, cyrillic), _)) => match bits.next() {
+                Some(true) => cyrillic,
+                Some(false) => latin,
+                None => c,
+            },
+            None => c,
+        })
+        .collect();
+    Some(result)
+}
+
+/// Decodes a secret written by `create_secret`.
+///
+/// Returns `None` if no terminator is found or the bytes are not valid UTF-8.
+pub fn extract_secret(message: &str) -> Option<String> {
+    let bits: Vec<bool> = message.chars().filter_map(|c| lookup(c).map(|(_, bit)| bit)).collect();
+    let bytes: Vec<u8> = bits
+        .chunks_exact(8)
+        .map(|chunk| chunk.iter().enumerate().fold(0u8, |byte, (i, &bit)| byte | (u8::from(bit) << i)))
+        .take_while(|&byte| byte != 0)
+        .collect();
+    if bytes.len() == bits.len() / 8 {
+        // Ran out of letters before reaching the terminator.
+        return None;
+    }
+    String::from_utf8(bytes).ok()
+}
+
+// Unit tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CARRIER: &str = "The export of paper today came back a complete mess, so every team has to repeat the process once more.";
+
+    #[test]
+    fn test_round_trip() {
+        for secret in ["", "hi", "key42"] {
+            let message = create_secret(CARRIER, secret).expect("Carrier too short");
+            assert_eq!(extract_secret(&message).as_deref(), Some(secret));
+        }
+    }
+
+    #[test]
+    fn test_preserves_length_and_shape() {
+        let message = create_secret(CARRIER, "hi").expect("Carrier too short");
+        assert_eq!(message.chars().count(), CARRIER.chars().count());
+        assert_ne!(message, CARRIER);
+
+        let latin: String = message.chars().map(|c| lookup(c).map_or(c, |((latin, _), _)| latin)).collect();
+        assert_eq!(latin, CARRIER);
+    }
+
+    #[test]
+    fn test_capacity() {
+        assert_eq!(capacity("abc"), 0);
+        assert_eq!(capacity(CARRIER), 5);
+        assert_eq!(capacity(&"a".repeat(16)), 1);
+        assert!(create_secret(&"a".repeat(16), "ab").is_none());
+        assert!(create_secret(&"a".repeat(24), "ab").is_some());
+        assert!(create_secret("abc", "").is_none());
+        assert!(create_secret(&"a".repeat(7), "").is_none());
+        assert!(create_secret(&"a".repeat(8), "").is_some());
+    }
+
+    #[test]
+    fn test_every_output_round_trips() {
+        for len in 0..40 {
+            let carrier = "ax".repeat(len);
+            for secret in ["", "h", "hi", "key"] {
+                if let Some(message) = create_secret(&carrier[..len], secret) {
+                    assert_eq!(extract_secret(&message).as_deref(), Some(secret), "carrier of {len} letters");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_rejects_nul() {
+        assert!(create_secret(CARRIER, "a\0b").is_none());
+    }
+
+    #[test]
+    fn test_never_panics() {
+        let inputs = ["", "\0", "🦀", "аааааааа", "e\u{301}", &"а".repeat(64), CARRIER];
+        for a in inputs {
+            let _ = (capacity(a), extract_secret(a));
+            for b in inputs {
+                let _ = create_secret(a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_missing_terminator() {
+        // Eight Cyrillic 'а' decode to 0xFF with no terminator after it.
+        assert_eq!(extract_secret(&"а".repeat(8)), None);
+    }
+}
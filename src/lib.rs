@@ -0,0 +1,8 @@
+//! Hides secret text inside ordinary text using zero-width characters.
+//!
+//! Public functions report bad input through `Option` or `Result` and never
+//! panic, so library code may not unwrap.
+#![cfg_attr(not(test), deny(clippy::unwrap_used, clippy::expect_used))]
+
+pub mod homoglyph;
+pub mod text_removal;
@@ -1,17 +1,35 @@
 #![allow(non_snake_case)]
 
 // Import Dioxus and necessary hooks/components
+use std::sync::Arc;
+
+use dioxus::html::{FileEngine, HasFileData};
 use dioxus::prelude::*;
 
 // Imports for clipboard functionality
 use gloo_timers::future::TimeoutFuture;
+use wasm_bindgen::{JsCast, JsValue};
 use wasm_bindgen_futures::JsFuture;
 
-mod text_removal;
+use evaporate::text_removal::{self, EscapeStyle};
+
+mod ui_logic;
+use ui_logic::{
+    detect_format, encode_error_message, hide_output, normalize_pasted, payload_survived, recovered_secret, seek_output,
+    span_descriptions, InputFormat,
+};
 
 const CONTAINER_CSS: Asset = asset!("assets/main.css");
 const NAVBAR_CSS: Asset = asset!("assets/navbar.css");
 
+/// Outputs larger than this many bytes are downloaded instead of copied, since
+/// some browsers fail or hang when writing multi-megabyte strings to the clipboard.
+const CLIPBOARD_LIMIT_BYTES: usize = 1024 * 1024;
+
+/// Invisible-to-visible character ratio above which the Hide page suggests a
+/// longer visible text.
+const MAX_INVISIBLE_RATIO: f64 = 0.5;
+
 #[derive(Routable, Clone, PartialEq)]
 enum Route {
     #[layout(Navbar)]
@@ -19,6 +37,8 @@ enum Route {
         Hide,
         #[route("/seek")]
         Seek, 
+        #[route("/how-it-works")]
+        HowItWorks,
         // #[route("/")]
         // #[redirect("/", || Route::Hide {} )]
     #[end_layout]
@@ -46,7 +66,7 @@ fn Navbar() -> Element {
     rsx! {
         div {
             class: "navbar-split",
-            div { class: "navbar-container",
+            nav { class: "navbar-container", aria_label: "Main",
                 Link {
                     to: Route::Hide,
                     active_class: "active",
@@ -57,6 +77,11 @@ fn Navbar() -> Element {
                     active_class: "active",
                     "Seek"
                 }
+                Link {
+                    to: Route::HowItWorks,
+                    active_class: "active",
+                    "How it works"
+                }
             }
             div { class: "page-container",
                 Outlet::<Route> {}
@@ -65,56 +90,420 @@ fn Navbar() -> Element {
     }
 }
 
+/// How the Hide page hands the combined message to the user.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CopyStrategy {
+    Clipboard,
+    Download,
+    /// The clipboard API is unavailable, e.g. over plain http. The output is
+    /// selected so the user can copy it themselves.
+    Manual,
+}
+
+/// Chooses how to hand over the output based on its size and on whether the
+/// clipboard API is available.
+fn copy_strategy(output_len: usize, clipboard_available: bool) -> CopyStrategy {
+    if output_len > CLIPBOARD_LIMIT_BYTES {
+        CopyStrategy::Download
+    } else if clipboard_available {
+        CopyStrategy::Clipboard
+    } else {
+        CopyStrategy::Manual
+    }
+}
+
+/// Returns the clipboard, if the browser exposes it. `navigator.clipboard` is
+/// `undefined` outside secure contexts, and calling into it would throw.
+fn clipboard() -> Option<web_sys::Clipboard> {
+    let navigator = web_sys::window()?.navigator();
+    let clipboard = js_sys::Reflect::get(&navigator, &JsValue::from_str("clipboard")).ok()?;
+    if clipboard.is_undefined() || clipboard.is_null() {
+        return None;
+    }
+    Some(navigator.clipboard())
+}
+
+/// Selects the contents of the textarea with the given id.
+fn select_textarea(id: &str) -> Option<()> {
+    let element = web_sys::window()?.document()?.get_element_by_id(id)?;
+    let textarea: web_sys::HtmlTextAreaElement = element.dyn_into().ok()?;
+    textarea.focus().ok()?;
+    textarea.select();
+    Some(())
+}
+
+/// Formats the visible text and the payload of `message` as a JSON object, so
+/// they can be pasted into separate fields.
+fn split_json(message: &str) -> String {
+    let (visible, payload) = text_removal::split_message(message);
+    let json = |s: &str| text_removal::to_escaped(s, EscapeStyle::Json);
+    format!("{{\"visible\": {}, \"payload\": {}}}", json(&visible), json(&payload))
+}
+
+/// Parses the value of the Hide page's "Copy as" selector. `None` copies the
+/// message as it is.
+fn copy_format(value: &str) -> Option<EscapeStyle> {
+    match value {
+        "rust" => Some(EscapeStyle::Rust),
+        "json" => Some(EscapeStyle::Json),
+        "python" => Some(EscapeStyle::Python),
+        _ => None,
+    }
+}
+
+/// Offers `text` to the user as a UTF-8 text file download.
+fn download_text(filename: &str, text: &str) -> Option<()> {
+    let document = web_sys::window()?.document()?;
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type("text/plain;charset=utf-8");
+    let parts = js_sys::Array::of1(&JsValue::from_str(text));
+    let blob = web_sys::Blob::new_with_str_sequence_and_options(&parts, &options).ok()?;
+
+    let url = web_sys::Url::create_object_url_with_blob(&blob).ok()?;
+    click_download(&document, &url, filename)?;
+    // The download has started, so the object URL can be released right away.
+    web_sys::Url::revoke_object_url(&url).ok()
+}
+
+/// Starts downloading `href` as `filename` through a temporary link.
+fn click_download(document: &web_sys::Document, href: &str, filename: &str) -> Option<()> {
+    let anchor: web_sys::HtmlAnchorElement = document.create_element("a").ok()?.dyn_into().ok()?;
+    anchor.set_href(href);
+    anchor.set_download(filename);
+    anchor.click();
+    Some(())
+}
+
+/// Characters per line on the share image.
+const SHARE_LINE_CHARS: usize = 40;
+
+/// Breaks `text` into lines of at most `width` characters at spaces. Words
+/// longer than a line are split.
+fn wrap_lines(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let mut word = word.to_string();
+            while word.chars().count() > width {
+                if !line.is_empty() {
+                    lines.push(std::mem::take(&mut line));
+                }
+                let split = word.char_indices().nth(width).map_or(word.len(), |(i, _)| i);
+                lines.push(word[..split].to_string());
+                word = word[split..].to_string();
+            }
+            if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&word);
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Draws the visible text of a message onto a canvas with a "contains a hidden
+/// message" badge and downloads it as a PNG.
+///
+/// The image only shows the visible text: invisible characters can't survive
+/// being drawn, so the payload has to be shared separately.
+fn download_share_image(visible: &str) -> Option<()> {
+    const LINE_HEIGHT: f64 = 28.0;
+    const PADDING: f64 = 24.0;
+
+    let document = web_sys::window()?.document()?;
+    let canvas: web_sys::HtmlCanvasElement = document.create_element("canvas").ok()?.dyn_into().ok()?;
+    let lines = wrap_lines(visible, SHARE_LINE_CHARS);
+    canvas.set_width(600);
+    canvas.set_height((PADDING * 3.0 + LINE_HEIGHT * (lines.len() + 1) as f64) as u32);
+    let context: web_sys::CanvasRenderingContext2d = canvas.get_context("2d").ok()??.dyn_into().ok()?;
+
+    context.set_fill_style_str("#ffffff");
+    context.fill_rect(0.0, 0.0, f64::from(canvas.width()), f64::from(canvas.height()));
+    context.set_fill_style_str("#202124");
+    context.set_font("20px sans-serif");
+    for (i, line) in lines.iter().enumerate() {
+        context.fill_text(line, PADDING, PADDING + LINE_HEIGHT * (i + 1) as f64).ok()?;
+    }
+
+    // The badge in the bottom left corner.
+    let badge_y = f64::from(canvas.height()) - PADDING - LINE_HEIGHT;
+    context.set_fill_style_str("#1a73e8");
+    context.fill_rect(PADDING, badge_y, 260.0, LINE_HEIGHT);
+    context.set_fill_style_str("#ffffff");
+    context.set_font("bold 14px sans-serif");
+    context.fill_text("🔒 Contains a hidden message", PADDING + 10.0, badge_y + 19.0).ok()?;
+
+    let url = canvas.to_data_url_with_type("image/png").ok()?;
+    click_download(&document, &url, "message.png")
+}
+
+/// Shows `message` as an error banner the user can dismiss. With `timeout_ms`
+/// set, it also dismisses itself after that long, like the copy button's label.
+#[component]
+fn ErrorBanner(message: Option<String>, timeout_ms: Option<u32>) -> Element {
+    let mut dismissed = use_signal(|| false);
+    // Bumped for every new message, so a timer started for an older one can't
+    // dismiss it.
+    let mut generation = use_signal(|| 0u32);
+    let shown = message.clone();
+    use_effect(use_reactive!(|(message, timeout_ms)| {
+        dismissed.set(false);
+        // Peeked, not read: reading would subscribe the effect to its own
+        // write below and rerun it forever.
+        let current = *generation.peek() + 1;
+        generation.set(current);
+        if let (Some(_), Some(timeout_ms)) = (message, timeout_ms) {
+            spawn(async move {
+                TimeoutFuture::new(timeout_ms).await;
+                if generation() == current {
+                    dismissed.set(true);
+                }
+            });
+        }
+    }));
+
+    match shown {
+        Some(message) if !dismissed() => rsx! {
+            div { class: "error-banner", role: "alert",
+                p { class: "error", "{message}" }
+                button {
+                    class: "inline-button",
+                    aria_label: "Dismiss",
+                    onclick: move |_| dismissed.set(true),
+                    "×"
+                }
+            }
+        },
+        _ => rsx! {},
+    }
+}
+
+/// Id of the Hide page's output textarea.
+const OUTPUT_ID: &str = "combined-output";
+
 #[component]
 fn Hide() -> Element {
     let mut visible_text = use_signal(|| String::from("Hello, World!"));
     let mut hidden_text = use_signal(|| String::from("Hidden text"));
-    let mut copy_button_text = use_signal(|| "Copy".to_string());
+    let copy_button_text = use_signal(|| "Copy".to_string());
+    let split_button_text = use_signal(|| "Copy visible text and payload as JSON".to_string());
+    let mut redacted = use_signal(|| false);
+    let mut copy_as = use_signal(|| None::<EscapeStyle>);
+    let mut copy_error = use_signal(|| None::<String>);
+    // The output as edited by the user, until the inputs change.
+    let mut edited_output = use_signal(|| None::<String>);
+    let mut edit_survived = use_signal(|| None::<bool>);
 
-    let visible = visible_text.cloned();
-    let hidden = hidden_text.cloned();
-    let output_text = text_removal::create_secret(&visible, &hidden);
+    let (encoded_text, encode_error) = match hide_output(&visible_text.read(), &hidden_text.read()) {
+        Ok(output) => (output, None),
+        Err(error) => (String::new(), Some(encode_error_message(&error))),
+    };
+    let output_text = edited_output().unwrap_or(encoded_text);
+    // Announced by screen readers when the copy button reports a result.
+    let copy_status = if copy_button_text() == "Copy" { String::new() } else { copy_button_text() };
+    let byte_count = output_text.len();
+    let scalar_count = text_removal::scalar_count(&output_text);
+    let grapheme_count = text_removal::grapheme_count(&output_text);
+    let detectability = text_removal::detectability(&output_text).score;
+    let missing_chars = text_removal::min_carrier_len(&hidden_text.read(), MAX_INVISIBLE_RATIO)
+        .saturating_sub(text_removal::scalar_count(&visible_text.read()));
+    let payload_at_edge = text_removal::payload_at_edge(&output_text);
+    let whitespace_only = text_removal::carrier_warning(&visible_text.read()).is_some();
+    let split_text = split_json(&output_text);
+    let share_text = text_removal::recover_carrier(&output_text);
+    let preview_text = if redacted() {
+        text_removal::redact_payload(&output_text, "⟦hidden⟧")
+    } else {
+        output_text.clone()
+    };
 
     rsx! {
         div { class: "widget-container",
             div { class: "input-group",
-                label { "Visible Text Input" }
+                label { r#for: "visible-text", "Visible Text Input" }
                 input {
+                    id: "visible-text",
                     r#type: "text",
                     placeholder: "Enter some text here...",
-                    oninput: move |event| visible_text.set(event.value())
+                    oninput: move |event| {
+                        visible_text.set(event.value());
+                        edited_output.set(None);
+                        edit_survived.set(None);
+                    }
                 }
             }
             div { class: "input-group",
-                label { "Hidden Text Input" }
+                label { r#for: "hidden-text", "Hidden Text Input" }
                 input {
+                    id: "hidden-text",
                     r#type: "text",
                     placeholder: "Enter secret text here...",
-                    oninput: move |event| hidden_text.set(event.value())
+                    oninput: move |event| {
+                        hidden_text.set(event.value());
+                        edited_output.set(None);
+                        edit_survived.set(None);
+                    }
+                }
+            }
+            div { class: "input-group",
+                label { class: "checkbox-label",
+                    input {
+                        r#type: "checkbox",
+                        checked: redacted(),
+                        onchange: move |event| redacted.set(event.checked())
+                    }
+                    "Redacted preview"
+                }
+            }
+            div { class: "input-group",
+                label { r#for: "copy-format", "Copy as" }
+                select {
+                    id: "copy-format",
+                    onchange: move |event| copy_as.set(copy_format(&event.value())),
+                    option { value: "text", "Text" }
+                    option { value: "rust", "Rust string literal" }
+                    option { value: "json", "JSON string" }
+                    option { value: "python", "Python string literal" }
                 }
             }
+            ErrorBanner { message: encode_error }
+            ErrorBanner { message: copy_error(), timeout_ms: 5000 }
             div { class: "pre-wrapper",
-                pre { "{output_text}" }
+                if redacted() {
+                    pre { aria_label: "Combined message, hidden text redacted", "{preview_text}" }
+                } else {
+                    textarea {
+                        id: OUTPUT_ID,
+                        class: "output",
+                        aria_label: "Combined message",
+                        value: "{output_text}",
+                        oninput: move |event| {
+                            edited_output.set(Some(event.value()));
+                            edit_survived.set(None);
+                        }
+                    }
+                }
                 button {
                     onclick: move |_| {
                         spawn({
-                            let to_copy = output_text.clone();
+                            let to_copy = match copy_as() {
+                                Some(style) => text_removal::to_escaped(&output_text, style),
+                                None => output_text.clone(),
+                            };
                             let mut button_text = copy_button_text;
                             async move {
-                                if let Some(window) = web_sys::window() {
-                                    let clipboard = window.navigator().clipboard();
-                                    let promise = clipboard.write_text(&to_copy);
-                                    if JsFuture::from(promise).await.is_ok() {
-                                        button_text.set("Copied!".to_string());
+                                let clipboard = clipboard();
+                                let label = match (copy_strategy(to_copy.len(), clipboard.is_some()), clipboard) {
+                                    (CopyStrategy::Download, _) => {
+                                        download_text("message.txt", &to_copy).map(|_| "Downloaded instead")
+                                    }
+                                    (CopyStrategy::Clipboard, Some(clipboard)) => {
+                                        let promise = clipboard.write_text(&to_copy);
+                                        JsFuture::from(promise).await.ok().map(|_| "Copied!")
+                                    }
+                                    _ => {
+                                        redacted.set(false);
+                                        select_textarea(OUTPUT_ID).map(|_| "Press Ctrl+C to copy")
+                                    }
+                                };
+                                match label {
+                                    Some(label) => {
+                                        copy_error.set(None);
+                                        button_text.set(label.to_string());
                                         TimeoutFuture::new(2000).await;
                                         button_text.set("Copy".to_string());
                                     }
+                                    None => copy_error.set(Some("Could not copy the message. Select it and copy it yourself.".to_string())),
                                 }
                             }
                         });
                     },
                     "{copy_button_text}"
                 }
+                span { class: "visually-hidden", role: "status", aria_live: "polite", "{copy_status}" }
+            }
+            div { class: "input-group",
+                button {
+                    class: "inline-button",
+                    onclick: {
+                        let to_copy = split_text;
+                        move |_| {
+                            let to_copy = to_copy.clone();
+                            let mut button_text = split_button_text;
+                            spawn(async move {
+                                let status = match clipboard() {
+                                    Some(clipboard) => match JsFuture::from(clipboard.write_text(&to_copy)).await {
+                                        Ok(_) => "Copied!",
+                                        Err(_) => return,
+                                    },
+                                    None => "Clipboard unavailable",
+                                };
+                                let label = button_text();
+                                button_text.set(status.to_string());
+                                TimeoutFuture::new(2000).await;
+                                button_text.set(label);
+                            });
+                        }
+                    },
+                    "{split_button_text}"
+                }
+            }
+            div { class: "input-group",
+                button {
+                    class: "inline-button",
+                    onclick: {
+                        let visible = share_text;
+                        move |_| {
+                            download_share_image(&visible);
+                        }
+                    },
+                    "Save share image"
+                }
+                p { class: "hint", "The image shows only the visible text. Share the message itself to pass on the hidden text." }
+            }
+            if edited_output().is_some() {
+                div { class: "input-group",
+                    button {
+                        class: "inline-button",
+                        onclick: {
+                            let edited = output_text.clone();
+                            move |_| edit_survived.set(Some(payload_survived(&edited, &hidden_text.read())))
+                        },
+                        "Re-extract to verify"
+                    }
+                    match edit_survived() {
+                        Some(true) => rsx! { p { role: "status", "The hidden text survived your edit." } },
+                        Some(false) => rsx! { p { class: "error", role: "alert", "Your edit damaged the hidden text. It can no longer be extracted." } },
+                        None => rsx! {},
+                    }
+                }
+            }
+            div { class: "output-stats",
+                p { "Bytes: {byte_count} · Characters: {scalar_count} · Visible characters: {grapheme_count}" }
+                p { class: "hint",
+                    "Zero-width characters are invisible, but most platforms still count them towards character limits."
+                }
+                if payload_at_edge {
+                    p { class: "hint",
+                        "The hidden text sits at the edge of the message, where some apps trim it. Use at least two visible characters."
+                    }
+                }
+                if whitespace_only {
+                    p { class: "hint",
+                        "The visible text is only whitespace. Apps that collapse or trim whitespace can lose the hidden text with it. Add some visible words."
+                    }
+                }
+                p { "Detectability: {detectability}/100" }
+                if missing_chars > 0 {
+                    p { class: "hint",
+                        "The hidden text is long compared to the visible text. Add at least {missing_chars} more characters to your visible text to make it harder to notice."
+                    }
+                }
             }
         }
     }
@@ -122,30 +511,259 @@ fn Hide() -> Element {
 
 #[component]
 fn Seek() -> Element {
-    let mut combined_text = use_signal(|| String::new());
-    let mut hidden_text = use_signal(|| String::new());
-
-    let combined = combined_text.cloned();
-    let hidden = text_removal::extract_secret(&combined);
+    let mut combined_text = use_signal(String::new);
+    let mut input_format = use_signal(|| InputFormat::Plain);
+    let mut dropped_file = use_signal(|| None::<String>);
+    let mut drop_error = use_signal(|| None::<String>);
+    let mut dragging = use_signal(|| false);
+    let hidden_text = seek_output(&combined_text.read(), *input_format.read());
+    let pasted = normalize_pasted(&combined_text.read());
+    let recovered = recovered_secret(&combined_text.read(), *input_format.read()).ok();
+    let spans = if recovered.is_some() { span_descriptions(&pasted) } else { Vec::new() };
 
-    match hidden {
-        Some(secret) => hidden_text.set(secret),
-        None => hidden_text.set("No hidden text found.".to_string()),
-    }
+    // Reads the first of the given files into the combined text.
+    let load_file = move |files: Arc<dyn FileEngine>| {
+        let Some(name) = files.files().into_iter().next() else {
+            return;
+        };
+        spawn(async move {
+            let Some(contents) = files.read_file_to_string(&name).await else {
+                drop_error.set(Some(format!("Could not read {name}.")));
+                return;
+            };
+            match detect_format(&name, &contents) {
+                Ok(format) => {
+                    combined_text.set(contents);
+                    input_format.set(format);
+                    dropped_file.set(Some(name));
+                    drop_error.set(None);
+                }
+                Err(error) => drop_error.set(Some(error)),
+            }
+        });
+    };
 
     rsx! {
         div { class: "widget-container",
             div { class: "input-group",
-                label { "Combined Text Input" }
+                label { r#for: "combined-text", "Combined Text Input" }
                 input {
+                    id: "combined-text",
                     r#type: "text",
                     placeholder: "Enter combined text here...",
-                    oninput: move |event| combined_text.set(event.value())
+                    value: "{combined_text}",
+                    oninput: move |event| {
+                        combined_text.set(event.value());
+                        input_format.set(InputFormat::Plain);
+                        dropped_file.set(None);
+                    }
                 }
             }
+            div {
+                class: if dragging() { "drop-zone dragging" } else { "drop-zone" },
+                ondragover: move |event| {
+                    event.prevent_default();
+                    dragging.set(true);
+                },
+                ondragleave: move |_| dragging.set(false),
+                ondrop: move |event| {
+                    event.prevent_default();
+                    dragging.set(false);
+                    if let Some(files) = event.files() {
+                        load_file(files);
+                    }
+                },
+                label { r#for: "combined-file", "Drop a .txt or .html file here, or choose one" }
+                input {
+                    id: "combined-file",
+                    r#type: "file",
+                    accept: ".txt,.html,.htm",
+                    onchange: move |event| {
+                        if let Some(files) = event.files() {
+                            load_file(files);
+                        }
+                    }
+                }
+                div { aria_live: "polite",
+                    if let Some(name) = dropped_file() {
+                        p { "Loaded {name}" }
+                    }
+                }
+                ErrorBanner { message: drop_error() }
+            }
             div { class: "output-container",
-                label { "Hidden Text Output" }
-                pre { "{hidden_text}" }
+                label { id: "hidden-output-label", "Hidden Text Output" }
+                pre { aria_labelledby: "hidden-output-label", aria_live: "polite", "{hidden_text}" }
+                if !spans.is_empty() {
+                    ul { class: "span-list",
+                        for description in spans {
+                            li { "{description}" }
+                        }
+                    }
+                    details {
+                        summary { "Show where" }
+                        p { class: "hint", "Each hidden character is shown as the bit it encodes." }
+                        pre { "{visualize_payload(&pasted)}" }
+                    }
+                }
+                if !text_removal::payload_bits(&pasted).is_empty() {
+                    details {
+                        summary { "Bit editor" }
+                        BitEditor { message: pasted.clone() }
+                    }
+                }
+                if let Some(secret) = recovered {
+                    button {
+                        class: "inline-button",
+                        // `download_text` releases its object URL as soon as the download starts.
+                        onclick: move |_| {
+                            download_text("hidden.txt", &secret);
+                        },
+                        "Download recovered text"
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Formats a character as a Unicode code point, e.g. `U+200C`.
+fn code_point(c: char) -> String {
+    format!("U+{:04X}", c as u32)
+}
+
+/// Makes the payload of a message visible by replacing each payload character
+/// with the bit it encodes.
+fn visualize_payload(message: &str) -> String {
+    let alphabet = text_removal::Alphabet::DEFAULT;
+    message
+        .chars()
+        .map(|c| match c {
+            c if c == alphabet.zero => '0',
+            c if c == alphabet.one => '1',
+            c => c,
+        })
+        .collect()
+}
+
+/// Describes what a sequence of payload bits decodes to, for the bit editor.
+fn decoded_bits(bits: &[bool]) -> String {
+    if !bits.len().is_multiple_of(8) {
+        return format!("{} bits are not a whole number of bytes.", bits.len());
+    }
+    text_removal::extract_secret(&text_removal::create_secret_from_bits("", bits))
+        .unwrap_or_else(|| "The bits are not valid UTF-8.".to_string())
+}
+
+/// Shows the payload of `message` as a grid of bits, one row per byte, that can
+/// be toggled to see how the secret changes.
+#[component]
+fn BitEditor(message: String) -> Element {
+    // Edited bits, together with the message they were taken from.
+    let mut edits = use_signal(|| None::<(String, Vec<bool>)>);
+    let bits = match edits() {
+        Some((edited, bits)) if edited == message => bits,
+        _ => text_removal::payload_bits(&message),
+    };
+    let decoded = decoded_bits(&bits);
+    let reencoded = text_removal::create_secret_from_bits(&text_removal::recover_carrier(&message), &bits);
+
+    rsx! {
+        p { class: "hint",
+            "Editing bits changes the secret directly. Checksums, such as those in beacons, tokens or "
+            "redundant copies, no longer match afterwards."
+        }
+        div { class: "bit-grid", role: "group", aria_label: "Payload bits",
+            for (row, byte) in bits.chunks(8).enumerate() {
+                div { class: "bit-row", key: "{row}",
+                    for (column, &bit) in byte.iter().enumerate() {
+                        button {
+                            class: "bit inline-button",
+                            aria_pressed: "{bit}",
+                            onclick: {
+                                let (message, mut bits) = (message.clone(), bits.clone());
+                                move |_| {
+                                    let index = row * 8 + column;
+                                    bits[index] = !bits[index];
+                                    edits.set(Some((message.clone(), bits.clone())));
+                                }
+                            },
+                            if bit { "1" } else { "0" }
+                        }
+                    }
+                }
+            }
+        }
+        p { "Decoded: {decoded}" }
+        label { r#for: "reencoded", "Re-encoded message" }
+        textarea { id: "reencoded", class: "output", readonly: true, value: "{reencoded}" }
+    }
+}
+
+/// Formats the payload bits of a character for a tooltip, one group per byte.
+fn char_bits(c: char) -> String {
+    let bits: Vec<String> = text_removal::visualize_char(c)
+        .chunks(8)
+        .map(|byte| byte.iter().map(|&bit| if bit { '1' } else { '0' }).collect())
+        .collect();
+    format!("'{c}' becomes {}", bits.join(" "))
+}
+
+#[component]
+fn HowItWorks() -> Element {
+    let mut show_payload = use_signal(|| false);
+    let alphabet = text_removal::Alphabet::DEFAULT;
+    let zero = code_point(alphabet.zero);
+    let one = code_point(alphabet.one);
+
+    let example = text_removal::create_secret("Hello", "Hi");
+    let example = if show_payload() { visualize_payload(&example) } else { example };
+
+    rsx! {
+        div { class: "widget-container help-panel",
+            details { open: true,
+                summary { "Zero-width characters" }
+                p {
+                    "Unicode has characters that take up no space when displayed. The secret is written "
+                    "with two of them, one for each bit value, so the combined message looks exactly like "
+                    "the visible text you typed."
+                }
+                ul {
+                    li { "{zero} (Zero-Width Non-Joiner) encodes a 0 bit." }
+                    li { "{one} (Zero-Width Joiner) encodes a 1 bit." }
+                }
+                p { "Every byte of the secret becomes 8 of these characters, inserted in the middle of the visible text." }
+            }
+            details {
+                summary { "See the hidden characters" }
+                label { class: "checkbox-label",
+                    input {
+                        r#type: "checkbox",
+                        checked: show_payload(),
+                        onchange: move |event| show_payload.set(event.checked())
+                    }
+                    "Show bits"
+                }
+                p { "\"Hello\" with the secret \"Hi\":" }
+                pre { "{example}" }
+                p {
+                    "Hover a letter of the secret to see its bits: "
+                    for c in "Hi".chars() {
+                        span { class: "bit-tooltip", title: "{char_bits(c)}", "{c}" }
+                    }
+                }
+            }
+            details {
+                summary { "Hiding and seeking" }
+                p {
+                    "On the "
+                    Link { to: Route::Hide, "Hide" }
+                    " page, enter the visible text and your secret, then copy the result. Whoever receives "
+                    "it pastes it into the "
+                    Link { to: Route::Seek, "Seek" }
+                    " page to read the secret."
+                }
+                p { "Some apps strip zero-width characters, so the secret may not survive every channel." }
             }
         }
     }
@@ -161,3 +779,151 @@ fn PageNotFound(route: Vec<String>) -> Element {
     // This component renders nothing, as it's only job is to trigger the redirect.
     rsx! {}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Renders a component once, without a browser, and returns its HTML.
+    fn render(component: fn() -> Element) -> String {
+        let mut dom = VirtualDom::new(component);
+        dom.rebuild_in_place();
+        dioxus_ssr::render(&dom)
+    }
+
+    #[test]
+    fn test_hide_renders_output() {
+        let html = render(Hide);
+        assert!(html.contains(&hide_output("Hello, World!", "Hidden text").unwrap()));
+    }
+
+    #[test]
+    fn test_copy_strategy() {
+        assert_eq!(copy_strategy(0, true), CopyStrategy::Clipboard);
+        assert_eq!(copy_strategy(CLIPBOARD_LIMIT_BYTES, true), CopyStrategy::Clipboard);
+        assert_eq!(copy_strategy(CLIPBOARD_LIMIT_BYTES + 1, true), CopyStrategy::Download);
+        assert_eq!(copy_strategy(0, false), CopyStrategy::Manual);
+        assert_eq!(copy_strategy(CLIPBOARD_LIMIT_BYTES, false), CopyStrategy::Manual);
+        assert_eq!(copy_strategy(CLIPBOARD_LIMIT_BYTES + 1, false), CopyStrategy::Download);
+    }
+
+    #[test]
+    fn test_split_json() {
+        let message = text_removal::create_secret("Say \"hi\"\n", "a");
+        let (_, payload) = text_removal::split_message(&message);
+        // 'a' is 0x61, written least significant bit first.
+        let escaped_payload = "\\u200d\\u200c\\u200c\\u200c\\u200c\\u200d\\u200d\\u200c";
+        assert_eq!(payload.chars().count(), 8);
+        assert_eq!(
+            split_json(&message),
+            format!("{{\"visible\": \"Say \\\"hi\\\"\\n\", \"payload\": \"{escaped_payload}\"}}")
+        );
+    }
+
+    #[test]
+    fn test_copy_format() {
+        assert_eq!(copy_format("text"), None);
+        assert_eq!(copy_format("rust"), Some(EscapeStyle::Rust));
+        assert_eq!(copy_format("json"), Some(EscapeStyle::Json));
+        assert_eq!(copy_format("python"), Some(EscapeStyle::Python));
+    }
+
+    #[test]
+    fn test_visualize_payload() {
+        let message = text_removal::create_secret("ab", "a");
+        // 'a' is 0x61, written least significant bit first.
+        assert_eq!(visualize_payload(&message), "a10000110b");
+    }
+
+    #[test]
+    fn test_decoded_bits() {
+        let bits = text_removal::payload_bits(&text_removal::create_secret("ab", "hi"));
+        assert_eq!(decoded_bits(&bits), "hi");
+        assert_eq!(decoded_bits(&bits[..12]), "12 bits are not a whole number of bytes.");
+        assert_eq!(decoded_bits(&[true; 8]), "The bits are not valid UTF-8.");
+    }
+
+    #[test]
+    fn test_bit_editor_renders_bits() {
+        let message = text_removal::create_secret("ab", "a");
+        let mut dom = VirtualDom::new_with_props(BitEditor, BitEditorProps { message });
+        dom.rebuild_in_place();
+        let html = dioxus_ssr::render(&dom);
+        assert_eq!(html.matches("class=\"bit inline-button\"").count(), 8);
+        assert_eq!(html.matches("aria-pressed=\"true\"").count(), 3);
+        assert!(html.contains("Decoded: a"));
+    }
+
+    #[test]
+    fn test_error_banner() {
+        let render_banner = |message: Option<&str>| {
+            let props = ErrorBannerProps { message: message.map(str::to_string), timeout_ms: None };
+            let mut dom = VirtualDom::new_with_props(ErrorBanner, props);
+            dom.rebuild_in_place();
+            dioxus_ssr::render(&dom)
+        };
+        let html = render_banner(Some("The carrier is empty."));
+        assert!(html.contains("role=\"alert\""));
+        assert!(html.contains("The carrier is empty."));
+        assert!(html.contains("aria-label=\"Dismiss\""));
+        assert!(!render_banner(None).contains("error-banner"));
+    }
+
+    #[tokio::test]
+    async fn test_error_banner_stays_dismissed() {
+        use dioxus::dioxus_core::Mutation;
+        use dioxus_html::{PlatformEventData, SerializedHtmlEventConverter, SerializedMouseData};
+        use std::rc::Rc;
+        use std::time::Duration;
+
+        dioxus_html::set_event_converter(Box::new(SerializedHtmlEventConverter));
+        let props = ErrorBannerProps { message: Some("The carrier is empty.".to_string()), timeout_ms: None };
+        let mut dom = VirtualDom::new_with_props(ErrorBanner, props);
+        let dismiss = dom
+            .rebuild_to_vec()
+            .edits
+            .iter()
+            .find_map(|edit| match edit {
+                Mutation::NewEventListener { name, id } if name == "click" => Some(*id),
+                _ => None,
+            })
+            .expect("no Dismiss button");
+        // Let the effect run for the first message before clicking.
+        let _ = tokio::time::timeout(Duration::from_millis(20), dom.wait_for_work()).await;
+        dom.render_immediate_to_vec();
+        assert!(dioxus_ssr::render(&dom).contains("error-banner"));
+
+        let click = PlatformEventData::new(Box::new(SerializedMouseData::default()));
+        let click = Event::new(Rc::new(click) as Rc<dyn std::any::Any>, true);
+        dom.runtime().handle_event("click", click, dismiss);
+
+        for _ in 0..5 {
+            let _ = tokio::time::timeout(Duration::from_millis(20), dom.wait_for_work()).await;
+            dom.render_immediate_to_vec();
+            assert!(!dioxus_ssr::render(&dom).contains("error-banner"));
+        }
+    }
+
+    #[test]
+    fn test_wrap_lines() {
+        assert_eq!(wrap_lines("Hello, World!", 40), ["Hello, World!"]);
+        assert_eq!(wrap_lines("one two three four", 9), ["one two", "three", "four"]);
+        assert_eq!(wrap_lines("Привет мир", 6), ["Привет", "мир"]);
+        assert_eq!(wrap_lines("abcdefgh ij", 3), ["abc", "def", "gh", "ij"]);
+        assert_eq!(wrap_lines("first\n\nsecond", 40), ["first", "", "second"]);
+    }
+
+    #[test]
+    fn test_char_bits() {
+        assert_eq!(char_bits('H'), "'H' becomes 00010010");
+        assert_eq!(char_bits('ж'), "'ж' becomes 00001011 01101101");
+    }
+
+    #[test]
+    fn test_seek_renders_empty_input() {
+        let html = render(Seek);
+        assert!(html.contains("Hidden Text Output"));
+        assert!(html.contains(&format!("aria-live=\"polite\">{}</pre>", seek_output("", InputFormat::Plain))));
+        assert!(html.contains("for=\"combined-text\""));
+    }
+}
@@ -1,4 +1,10 @@
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+
+#[cfg(feature = "regex")]
 use regex::Regex;
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
 
 /// Zero-Width Non-Joiner (U+200C), used to represent a '0' bit.
 const I_0: &str = "\u{200C}";
@@ -40,6 +46,10 @@ fn encode_byte(byte: u8) -> String {
 ///
 /// An `Option<u8>` containing the decoded byte if successful, or `None` if the
 /// input string has an incorrect length.
+///
+/// Only kept as the reference `decode_byte_table` is tested and benchmarked
+/// against.
+#[cfg(any(test, feature = "bench-internals"))]
 fn decode_byte(data: &str) -> Option<u8> {
     if data.len() != 8 * EXP_SIZE {
         return None;
@@ -58,6 +68,23 @@ fn decode_byte(data: &str) -> Option<u8> {
     Some(result)
 }
 
+/// Maps the last UTF-8 byte of a payload character to its bit. `I_0` and `I_1`
+/// share their first two bytes and differ only in the last one.
+const BIT_OF_LAST_BYTE: [u8; 256] = {
+    let mut table = [0; 256];
+    table[I_1.as_bytes()[EXP_SIZE - 1] as usize] = 1;
+    table
+};
+
+/// Decodes `8 * EXP_SIZE` bytes of payload characters into a single byte.
+///
+/// Looking up each character's last byte is faster than comparing whole
+/// characters, which matters when scanning large inputs. The chunk must only
+/// contain `I_0` and `I_1`, as `remove_unnecessary_symbols` guarantees.
+fn decode_byte_table(chunk: &[u8]) -> u8 {
+    (0..8).fold(0, |byte, i| byte | (BIT_OF_LAST_BYTE[chunk[(i + 1) * EXP_SIZE - 1] as usize] << i))
+}
+
 /// Encodes a string slice into a sequence of zero-width characters.
 ///
 /// # Arguments
@@ -68,7 +95,20 @@ fn decode_byte(data: &str) -> Option<u8> {
 ///
 /// A `String` containing the full encoded message.
 fn encode(data: &str) -> String {
-    data.bytes().map(encode_byte).collect()
+    encode_bytes(data.as_bytes())
+}
+
+/// Encodes arbitrary bytes into a sequence of zero-width characters.
+///
+/// # Arguments
+///
+/// * `data` - The bytes to encode.
+///
+/// # Returns
+///
+/// A `String` containing 8 zero-width characters per input byte.
+fn encode_bytes(data: &[u8]) -> String {
+    data.iter().copied().map(encode_byte).collect()
 }
 
 /// Decodes a string of zero-width characters back into the original string.
@@ -81,24 +121,54 @@ fn encode(data: &str) -> String {
 ///
 /// An `Option<String>` containing the decoded string if successful, or `None` if the
 /// input is malformed (e.g., wrong length, invalid UTF-8).
+///
+/// A non-empty run made only of `I_0` would decode to NUL characters. Such runs
+/// are more likely stray U+200C formatting characters than a secret, so they
+/// are rejected as well. A secret made only of NULs therefore can't be decoded.
 fn decode(data: &str) -> Option<String> {
-    if data.len() % (8 * EXP_SIZE) != 0 {
+    if !data.is_empty() && !data.contains(I_1) {
         return None;
     }
-
-    let bytes: Option<Vec<u8>> = data
-        .as_bytes()
-        .chunks_exact(8 * EXP_SIZE)
-        .map(|chunk| {
-            // The chunk must be valid UTF-8 to be decoded as a str
-            let s = std::str::from_utf8(chunk).ok()?;
-            decode_byte(s)
-        })
-        .collect();
-
     // from_utf8 converts the vector of bytes back into a String.
     // This can also fail if the resulting bytes are not valid UTF-8.
-    bytes.and_then(|b| String::from_utf8(b).ok())
+    decode_bytes(data).and_then(|b| String::from_utf8(b).ok())
+}
+
+/// Decodes a string of zero-width characters back into raw bytes.
+///
+/// # Arguments
+///
+/// * `data` - The encoded string of zero-width characters.
+///
+/// # Returns
+///
+/// An `Option<Vec<u8>>` containing the decoded bytes, or `None` if the input
+/// length isn't a whole number of encoded bytes.
+fn decode_bytes(data: &str) -> Option<Vec<u8>> {
+    if !data.len().is_multiple_of(8 * EXP_SIZE) {
+        return None;
+    }
+
+    Some(data.as_bytes().chunks_exact(8 * EXP_SIZE).map(decode_byte_table).collect())
+}
+
+/// Like `decode_bytes`, but decodes each byte with the loop in `decode_byte`,
+/// so the benches can compare the two on the same input.
+#[cfg(feature = "bench-internals")]
+#[doc(hidden)]
+pub fn decode_bytes_loop(data: &str) -> Option<Vec<u8>> {
+    if !data.len().is_multiple_of(8 * EXP_SIZE) {
+        return None;
+    }
+
+    (0..data.len()).step_by(8 * EXP_SIZE).map(|i| decode_byte(data.get(i..i + 8 * EXP_SIZE)?)).collect()
+}
+
+/// `decode_bytes`, exposed for the benches.
+#[cfg(feature = "bench-internals")]
+#[doc(hidden)]
+pub fn decode_bytes_table(data: &str) -> Option<Vec<u8>> {
+    decode_bytes(data)
 }
 
 /// Filters a string, returning only the zero-width characters used for encoding.
@@ -110,122 +180,3488 @@ fn decode(data: &str) -> Option<String> {
 /// # Returns
 ///
 /// A `String` containing only the `I_0` and `I_1` characters.
+#[cfg(feature = "regex")]
 fn remove_unnecessary_symbols(data: &str) -> String {
     // Using a regex is more robust than byte-wise iteration from the C++ version.
-    // It correctly handles all Unicode characters, not just ASCII.
-    let re = Regex::new(&format!("[{}|{}]", I_0, I_1)).unwrap();
-    re.find_iter(data).map(|mat| mat.as_str()).collect()
+    // It correctly handles all Unicode characters, not just ASCII. It is built
+    // once; should that ever fail, the byte scan gives the same result.
+    static RE: std::sync::OnceLock<Option<Regex>> = std::sync::OnceLock::new();
+    match RE.get_or_init(|| Regex::new(&format!("[{}{}]", I_0, I_1)).ok()) {
+        Some(re) => re.find_iter(data).map(|mat| mat.as_str()).collect(),
+        None => remove_unnecessary_symbols_scan(data),
+    }
 }
 
-/// High-level function to encode a secret message. Alias for `encode`.
-pub fn create_secret(normal_str: &str, secret: &str) -> String {
-    let mut mid = normal_str.len() / 2;
-    while !normal_str.is_char_boundary(mid) {
-        mid -= 1;
+/// Filters a string, returning only the zero-width characters used for encoding.
+/// Used instead of the regex when the `regex` feature is disabled, to keep the
+/// dependency out of small WASM builds.
+#[cfg(not(feature = "regex"))]
+fn remove_unnecessary_symbols(data: &str) -> String {
+    remove_unnecessary_symbols_scan(data)
+}
+
+/// Byte-scan implementation of `remove_unnecessary_symbols`.
+///
+/// It matches the 3-byte UTF-8 sequences of `I_0` and `I_1` directly. That is
+/// safe on any valid UTF-8 input because their first byte (0xE2) is a lead byte
+/// and can't occur inside another character.
+fn remove_unnecessary_symbols_scan(data: &str) -> String {
+    let bytes = data.as_bytes();
+    let mut result = String::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let rest = &bytes[i..];
+        if rest.starts_with(I_0.as_bytes()) {
+            result.push_str(I_0);
+            i += EXP_SIZE;
+        } else if rest.starts_with(I_1.as_bytes()) {
+            result.push_str(I_1);
+            i += EXP_SIZE;
+        } else {
+            i += 1;
+        }
+    }
+    result
+}
+
+/// Moves the byte index `idx` back to the nearest char boundary of `s`. Indices
+/// past the end are clamped to `s.len()`.
+///
+/// All insertion points go through this, so the loop is bounded: a UTF-8
+/// character is at most 4 bytes, so at most 3 steps are taken.
+fn snap_to_char_boundary(s: &str, idx: usize) -> usize {
+    let mut idx = idx.min(s.len());
+    // Index 0 is always a boundary, so this can't underflow.
+    while !s.is_char_boundary(idx) {
+        idx -= 1;
     }
+    idx
+}
+
+/// Returns the byte midpoint of `s`, moved back to the nearest char boundary.
+fn midpoint(s: &str) -> usize {
+    snap_to_char_boundary(s, s.len() / 2)
+}
 
+/// High-level function to encode a secret message.
+///
+/// The encoded secret is inserted at the byte midpoint of `normal_str`. If the
+/// midpoint falls inside a multi-byte character, it is moved back to the start
+/// of that character, so for a single-character carrier the payload goes in
+/// front of the visible text.
+///
+/// Some input fields and messengers trim whitespace and zero-width characters
+/// at the edges of a text. With at least two visible characters the payload is
+/// always surrounded by visible text and survives that; `payload_at_edge` tells
+/// whether a message is exposed.
+pub fn create_secret(normal_str: &str, secret: &str) -> String {
+    let mid = midpoint(normal_str);
     let hidden_content = encode(secret);
     format!("{}{}{}", &normal_str[..mid], hidden_content, &normal_str[mid..])
 }
 
-/// High-level function to find and decode a secret message from a larger string.
-pub fn extract_secret(message: &str) -> Option<String> {
-    let filtered = remove_unnecessary_symbols(message);
-    decode(&filtered)
+/// Like `create_secret`, but returns `normal_str` itself, without allocating,
+/// when `secret` is empty and there is nothing to insert.
+pub fn create_secret_cow<'a>(normal_str: &'a str, secret: &str) -> Cow<'a, str> {
+    if secret.is_empty() {
+        Cow::Borrowed(normal_str)
+    } else {
+        Cow::Owned(create_secret(normal_str, secret))
+    }
 }
 
+/// Like `create_secret`, but runs the secret's bytes through `transform` (e.g. a
+/// custom cipher or compression) before they are encoded.
+///
+/// Nothing in the payload records that a transform was used, so the reader must
+/// call `extract_secret_transformed` with the inverse.
+pub fn create_secret_transformed(normal_str: &str, secret: &str, transform: impl Fn(&[u8]) -> Vec<u8>) -> String {
+    let mid = midpoint(normal_str);
+    let hidden_content = encode_bytes(&transform(secret.as_bytes()));
+    format!("{}{}{}", &normal_str[..mid], hidden_content, &normal_str[mid..])
+}
 
-// Unit tests
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use rstest::rstest;
+/// Decodes a message produced by `create_secret_transformed`, applying `inverse`
+/// to the payload bytes before interpreting them as UTF-8.
+pub fn extract_secret_transformed(message: &str, inverse: impl Fn(&[u8]) -> Vec<u8>) -> Option<String> {
+    let bytes = extract_raw(message)?;
+    String::from_utf8(inverse(&bytes)).ok()
+}
 
-    #[test]
-    fn assert_correct_size() {
-        assert_eq!(I_0.len(), EXP_SIZE);
-        assert_eq!(I_1.len(), EXP_SIZE);
+/// Returns the visible text of `message`, with all payload characters removed.
+pub fn recover_carrier(message: &str) -> String {
+    message.chars().filter(|&c| !Alphabet::DEFAULT.contains(c)).collect()
+}
+
+/// Splits `message` into its visible text and its payload characters.
+///
+/// Appending the payload to the visible text again yields a message with the
+/// same secret, though not necessarily at the original position.
+pub fn split_message(message: &str) -> (String, String) {
+    let (payload, visible) = message.chars().partition(|&c| Alphabet::DEFAULT.contains(c));
+    (visible, payload)
+}
+
+/// Re-encodes the secret of `message` the way `create_secret` would, so messages
+/// with the same visible text and secret compare equal however they were built
+/// (e.g. spread or inserted in the middle).
+///
+/// A message without a decodable payload is returned unchanged.
+pub fn canonicalize(message: &str) -> String {
+    reflow(message).unwrap_or_else(|| message.to_string())
+}
+
+/// Repairs a message whose visible text was edited by hand: the secret is
+/// re-inserted as a single run at the midpoint of the current visible text.
+///
+/// Returns `None` if the payload no longer decodes.
+pub fn reflow(message: &str) -> Option<String> {
+    let secret = extract_secret(message)?;
+    Some(create_secret(&recover_carrier(message), &secret))
+}
+
+/// Moves the secret of `message` into `new_carrier`, e.g. to swap the cover
+/// story, inserting it like `create_secret` does.
+///
+/// Returns `None` if `message` carries no secret or one that doesn't decode.
+pub fn rewrap(message: &str, new_carrier: &str) -> Option<String> {
+    let secret = try_extract_secret(message).ok()?;
+    Some(create_secret(new_carrier, &secret))
+}
+
+/// Compares two carriers, optionally after NFC-normalizing both, so composed
+/// and decomposed forms of the same text (`é` vs `e` + U+0301) are equal.
+fn same_carrier(a: &str, b: &str, normalize: bool) -> bool {
+    if normalize {
+        a.nfc().eq(b.nfc())
+    } else {
+        a == b
     }
+}
 
-    #[test]
-    fn test_encode_decode_byte() {
-        println!("Testing encode_decode_byte");
-        let c = 'a';
-        let result = encode_byte(c as u8);
+/// Largest secret, in bytes, accepted by the validating encoders. Each byte
+/// becomes 24 bytes of zero-width characters, so this caps the payload at 24 MiB,
+/// far beyond what any text channel accepts.
+pub const MAX_SECRET_LEN: usize = 1024 * 1024;
 
-        assert_eq!(result.len(), 8 * EXP_SIZE);
-        
-        let decoded_char = decode_byte(&result).expect("Decoding failed");
-        assert_eq!(decoded_char, c as u8);
+/// Reasons the validating encoders can refuse to build a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncodeError {
+    /// The carrier is empty, so the message would consist of invisible
+    /// characters only.
+    EmptyCarrier,
+    /// The carrier already contains characters of the alphabet, which would be
+    /// mixed into the payload on extraction.
+    CarrierContainsAlphabet,
+    /// The secret is longer than `max` bytes.
+    PayloadTooLarge { len: usize, max: usize },
+    /// The finished message did not decode back to the secret and carrier.
+    RoundTripFailed,
+    /// The alphabet can't carry a payload.
+    AlphabetInvalid(AlphabetError),
+    /// The text to insert the payload after doesn't occur in the carrier.
+    NeedleNotFound,
+    /// The text to insert the payload after is empty, so there is nothing to find.
+    EmptyNeedle,
+    /// The carrier contains a non-ASCII character, but ASCII-only output was requested.
+    NonAsciiCarrier(char),
+    /// The carrier and payload already take `needed` characters, more than the
+    /// requested fixed total.
+    ExceedsFixedTotal { needed: usize, target: usize },
+}
+
+impl std::fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EncodeError::EmptyCarrier => write!(f, "the carrier is empty"),
+            EncodeError::CarrierContainsAlphabet => write!(f, "the carrier already contains payload characters"),
+            EncodeError::PayloadTooLarge { len, max } => {
+                write!(f, "the secret is {len} bytes long, the maximum is {max}")
+            }
+            EncodeError::RoundTripFailed => write!(f, "the message does not decode back to its input"),
+            EncodeError::AlphabetInvalid(error) => write!(f, "invalid alphabet: {error}"),
+            EncodeError::NeedleNotFound => write!(f, "the carrier doesn't contain the requested text"),
+            EncodeError::EmptyNeedle => write!(f, "the text to insert the payload after is empty"),
+            EncodeError::NonAsciiCarrier(c) => write!(f, "the carrier contains the non-ASCII character {c:?}"),
+            EncodeError::ExceedsFixedTotal { needed, target } => {
+                write!(f, "the message needs {needed} characters, more than the fixed total of {target}")
+            }
+        }
     }
+}
 
-    #[test]
-    fn test_encode_decode_ascii() {
-        println!("Testing encode_decode_ascii");
-        let data = "Hello, World!";
-        let encoded = encode(data);
-        let decoded = decode(&encoded).expect("Decoding failed");
-        assert_eq!(data, decoded);
+impl std::error::Error for EncodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EncodeError::AlphabetInvalid(error) => Some(error),
+            _ => None,
+        }
     }
+}
 
-    #[test]
-    fn test_encode_decode_other() {
-        println!("Testing encode_decode_other");
-        // Cyrillic characters to test multi-byte UTF-8 handling
-        let data = "ДАРОВА БРАТВА!";
-        let encoded = encode(data);
-        let decoded = decode(&encoded).expect("Decoding failed");
-        assert_eq!(data, decoded);
+/// Checks the inputs shared by all validating encoders.
+fn check_inputs(normal_str: &str, secret: &str, alphabet: &Alphabet) -> Result<(), EncodeError> {
+    validate_alphabet(alphabet).map_err(EncodeError::AlphabetInvalid)?;
+    if normal_str.is_empty() {
+        return Err(EncodeError::EmptyCarrier);
+    }
+    if secret.len() > MAX_SECRET_LEN {
+        return Err(EncodeError::PayloadTooLarge { len: secret.len(), max: MAX_SECRET_LEN });
+    }
+    if normal_str.chars().any(|c| alphabet.contains(c)) {
+        return Err(EncodeError::CarrierContainsAlphabet);
     }
+    Ok(())
+}
 
-    #[test]
-    fn test_full_pipeline() {
-        println!("Testing full pipeline");
-        let secret = "суперsecret";
-        let mixed_message = create_secret("Hello world", secret);
-        
-        let extracted_secret = extract_secret(&mixed_message).expect("Extraction failed");
-        assert_eq!(secret, extracted_secret);
+/// Like `create_secret`, but writes the payload with the given alphabet and
+/// validates the inputs first.
+pub fn create_secret_with(normal_str: &str, secret: &str, alphabet: &Alphabet) -> Result<String, EncodeError> {
+    check_inputs(normal_str, secret, alphabet)?;
+    let (head, tail) = normal_str.split_at(midpoint(normal_str));
+    let mut result = String::with_capacity(normal_str.len() + secret.len() * 8 * EXP_SIZE);
+    result.push_str(head);
+    result.extend(alphabet.encode(secret));
+    result.push_str(tail);
+    Ok(result)
+}
+
+/// Checks that `message` decodes back to `secret` and to the visible text `normal_str`.
+fn verify_round_trip(message: &str, normal_str: &str, secret: &str, normalize: bool) -> Result<(), EncodeError> {
+    let received = if normalize { message.nfc().collect() } else { message.to_string() };
+
+    let secret_ok = extract_secret(&received).as_deref() == Some(secret);
+    let carrier_ok = same_carrier(&recover_carrier(&received), normal_str, normalize);
+    if secret_ok && carrier_ok {
+        Ok(())
+    } else {
+        Err(EncodeError::RoundTripFailed)
     }
-    
-    #[test]
-    fn test_extraction_from_complex_text() {
-        println!("Testing extraction from complex text");
-        let secret = "TopSecret123";
-        
-        let message = create_secret("Это тест, а вот и продолжение спрятанного сообщения", secret);
+}
 
-        let extracted = extract_secret(&message).expect("Extraction failed");
-        assert_eq!(secret, extracted);
+/// Carriers that work, but make the message fragile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CarrierWarning {
+    /// The carrier consists of whitespace only. Channels that collapse or trim
+    /// whitespace can drop it along with the payload, and a blank message with
+    /// invisible characters in it stands out. A carrier with visible words
+    /// avoids both, and it also allows `homoglyph` encoding if needed.
+    WhitespaceOnlyCarrier,
+}
+
+impl std::fmt::Display for CarrierWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CarrierWarning::WhitespaceOnlyCarrier => write!(f, "the carrier consists of whitespace only"),
+        }
     }
+}
 
-    #[rstest]
-    fn test_all_combinations(
-        #[values(
-            "Hello", "secret123", "A quick brown fox.", "!@#$%^&*()_+",
-            "你好世界", "Rustacean 🦀", "Привет", "δοκιμή",
-            "", " ", "\n\t",
-            "a-very-long-string-that-tests-buffer-handling-and-other-edge-cases",
-            "another-similarly-long-string-to-ensure-no-static-assumptions-are-made",
-            "Text with 123 and some Cyrillic: Привет", "你好 with some emoji 😃 and numbers 456",
-            "json-like:{'key':'value'}", "<html><body>test</body></html>", "a final test string"
-        )] 
-        base: &str,
-        #[values(
-            "Hello", "secret123", "A quick brown fox.", "!@#$%^&*()_+",
-            "你好世界", "Rustacean 🦀", "Привет", "δοκιμή",
-            "", " ", "\n\t",
-            "a-very-long-string-that-tests-buffer-handling-and-other-edge-cases",
-            "another-similarly-long-string-to-ensure-no-static-assumptions-are-made",
-            "Text with 123 and some Cyrillic: Привет", "你好 with some emoji 😃 and numbers 456",
-            "json-like:{'key':'value'}", "<html><body>test</body></html>", "a final test string"
-        )] secret: &str
-    ) {
-        // The test logic remains the same
-        let message = create_secret(base, secret);
-        let extracted = extract_secret(&message).expect("Extraction failed");
-        assert_eq!(secret, extracted);
+/// Returns a warning if `carrier` can carry a payload but makes it fragile.
+pub fn carrier_warning(carrier: &str) -> Option<CarrierWarning> {
+    (!carrier.is_empty() && carrier.chars().all(char::is_whitespace)).then_some(CarrierWarning::WhitespaceOnlyCarrier)
+}
+
+/// Like `create_secret`, but validates the inputs and verifies that the message
+/// round-trips before returning it: the secret must be extractable and the
+/// visible text must be the original carrier.
+///
+/// With `normalize` set, the message is first NFC-normalized, as many channels
+/// do in transit, and the carriers are compared in NFC.
+pub fn create_secret_checked(normal_str: &str, secret: &str, normalize: bool) -> Result<String, EncodeError> {
+    check_inputs(normal_str, secret, &Alphabet::DEFAULT)?;
+    let message = create_secret(normal_str, secret);
+    verify_round_trip(&message, normal_str, secret, normalize)?;
+    Ok(message)
+}
+
+/// Filler appended to the carrier by `create_secret_fixed`.
+const FILLER: char = ' ';
+/// First byte of a payload written by `create_secret_fixed`. It never starts
+/// valid UTF-8, so `extract_secret_fixed` can't mistake a plain payload for one.
+const FIXED_MARKER: u8 = 0xFB;
+
+/// Like `create_secret_checked`, but with `fixed_total` set every message is
+/// exactly that many characters long, whatever the length of the secret, so the
+/// output size leaks nothing.
+///
+/// The secret is prefixed with a marker byte and its length as a varint, one
+/// byte for secrets shorter than 128 bytes. With `fixed_total` set, the payload is then padded
+/// with NUL bytes, eight characters each, and the carrier with up to seven
+/// trailing spaces for the rest; without it nothing is padded. Either way, use
+/// `extract_secret_fixed` to decode the message. Since the length is stored,
+/// NULs in the secret itself, leading or trailing, survive. The padded payload
+/// is subject to `MAX_SECRET_LEN` like any secret.
+pub fn create_secret_fixed(normal_str: &str, secret: &str, fixed_total: Option<usize>) -> Result<String, EncodeError> {
+    check_inputs(normal_str, secret, &Alphabet::DEFAULT)?;
+    let mut payload = vec![FIXED_MARKER];
+    push_varint(&mut payload, secret.len());
+    payload.extend_from_slice(secret.as_bytes());
+    let Some(target) = fixed_total else {
+        let (head, tail) = normal_str.split_at(midpoint(normal_str));
+        return Ok(format!("{}{}{}", head, encode_bytes(&payload), tail));
+    };
+    let needed = normal_str.chars().count() + payload.len() * 8;
+    if needed > target {
+        return Err(EncodeError::ExceedsFixedTotal { needed, target });
+    }
+
+    let spare = target - needed;
+    let padded = payload.len() + spare / 8;
+    if padded > MAX_SECRET_LEN {
+        return Err(EncodeError::PayloadTooLarge { len: padded, max: MAX_SECRET_LEN });
+    }
+    payload.resize(padded, 0);
+    let carrier: String = normal_str.chars().chain(std::iter::repeat_n(FILLER, spare % 8)).collect();
+
+    let (head, tail) = carrier.split_at(midpoint(&carrier));
+    Ok(format!("{}{}{}", head, encode_bytes(&payload), tail))
+}
+
+/// Decodes a message produced by `create_secret_fixed`, dropping the padding.
+pub fn extract_secret_fixed(message: &str) -> Option<String> {
+    let bytes = extract_raw(message)?;
+    let (&FIXED_MARKER, rest) = bytes.split_first()? else {
+        return None;
+    };
+    let (len, rest) = take_varint(rest)?;
+    let secret = rest.get(..len)?;
+    String::from_utf8(secret.to_vec()).ok()
+}
+
+/// Returns the bits a single character becomes in the payload: 8 per UTF-8
+/// byte, each byte least significant bit first.
+pub fn visualize_char(c: char) -> Vec<bool> {
+    payload_bits(&encode(c.encode_utf8(&mut [0; 4])))
+}
+
+/// Returns the bits carried by the payload characters of `message`, in order.
+pub fn payload_bits(message: &str) -> Vec<bool> {
+    message
+        .chars()
+        .filter(|&c| Alphabet::DEFAULT.contains(c))
+        .map(|c| c == Alphabet::DEFAULT.one)
+        .collect()
+}
+
+/// Inserts `bits` as payload characters in the middle of `normal_str`, like
+/// `create_secret` does with the bits of a secret.
+pub fn create_secret_from_bits(normal_str: &str, bits: &[bool]) -> String {
+    let (head, tail) = normal_str.split_at(midpoint(normal_str));
+    let payload = bits.iter().map(|&bit| if bit { I_1 } else { I_0 });
+    let mut result = String::with_capacity(normal_str.len() + bits.len() * EXP_SIZE);
+    result.push_str(head);
+    result.extend(payload);
+    result.push_str(tail);
+    result
+}
+
+/// Returns the payload `create_secret` would insert for `secret`, without any
+/// carrier.
+pub fn encode_only(secret: &str) -> String {
+    encode(secret)
+}
+
+/// Returns just the payload for `secret`, to be appended to `existing` by the
+/// caller, after checking that `existing` can carry it.
+///
+/// Fails like `create_secret_with` does, e.g. if `existing` already contains
+/// payload characters, which would be mixed into the secret. Note that a payload
+/// at the very end of a message can be lost to edge trimming (see
+/// `payload_at_edge`).
+pub fn payload_for_append(existing: &str, secret: &str) -> Result<String, EncodeError> {
+    check_inputs(existing, secret, &Alphabet::DEFAULT)?;
+    Ok(encode_only(secret))
+}
+
+/// Like `create_secret_checked`, but also guarantees that the visible text of the
+/// result is pure ASCII, for channels that reject anything else. The payload
+/// characters themselves are of course not ASCII.
+pub fn create_secret_ascii(normal_str: &str, secret: &str) -> Result<String, EncodeError> {
+    if let Some(c) = normal_str.chars().find(|c| !c.is_ascii()) {
+        return Err(EncodeError::NonAsciiCarrier(c));
+    }
+    create_secret_checked(normal_str, secret, false)
+}
+
+/// Like `create_secret_ascii`, but transliterates a non-ASCII carrier to ASCII
+/// first (e.g. "Привет" becomes "Privet") instead of rejecting it.
+#[cfg(feature = "transliterate")]
+pub fn create_secret_transliterated(normal_str: &str, secret: &str) -> Result<String, EncodeError> {
+    create_secret_ascii(&deunicode::deunicode(normal_str), secret)
+}
+
+/// Carrier used by `create_secret_guarded` when asked to fill in an empty one.
+pub const DEFAULT_CARRIER: &str = "Thanks, talk to you soon!";
+
+/// What `create_secret_guarded` does when the carrier is empty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EmptyCarrier {
+    /// Fail with `EncodeError::EmptyCarrier`.
+    #[default]
+    Reject,
+    /// Hide the secret in `DEFAULT_CARRIER` instead.
+    UseDefault,
+}
+
+/// Like `create_secret_checked`, but lets the caller choose what happens with
+/// an empty carrier instead of always rejecting it.
+pub fn create_secret_guarded(normal_str: &str, secret: &str, on_empty: EmptyCarrier) -> Result<String, EncodeError> {
+    let carrier = match on_empty {
+        EmptyCarrier::UseDefault if normal_str.is_empty() => DEFAULT_CARRIER,
+        _ => normal_str,
+    };
+    create_secret_checked(carrier, secret, false)
+}
+
+/// First byte of a tamper beacon. It never occurs in UTF-8, so a beacon can't be
+/// mistaken for a text secret.
+const BEACON_MARKER: u8 = 0xFF;
+
+/// Computes the CRC-32 (IEEE 802.3) checksum of `data`.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// State of a tamper beacon, as reported by `verify_beacon`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BeaconStatus {
+    /// The visible text matches the checksum stored in the beacon.
+    Intact,
+    /// A beacon is present but the visible text has changed since it was embedded.
+    Modified,
+    /// The message carries no beacon.
+    NoBeacon,
+}
+
+/// Hides a checksum of `visible` in its middle, so later edits to the visible
+/// text can be detected with `verify_beacon`. No secret is embedded.
+pub fn embed_beacon(visible: &str) -> String {
+    let mut beacon = vec![BEACON_MARKER];
+    beacon.extend_from_slice(&crc32(visible.as_bytes()).to_be_bytes());
+
+    let (head, tail) = visible.split_at(midpoint(visible));
+    format!("{}{}{}", head, encode_bytes(&beacon), tail)
+}
+
+/// Checks a message produced by `embed_beacon` against its current visible text.
+pub fn verify_beacon(message: &str) -> BeaconStatus {
+    let payload = extract_raw(message);
+    let Some(&[BEACON_MARKER, a, b, c, d]) = payload.as_deref() else {
+        return BeaconStatus::NoBeacon;
+    };
+    let checksum = u32::from_be_bytes([a, b, c, d]);
+
+    if crc32(recover_carrier(message).as_bytes()) == checksum {
+        BeaconStatus::Intact
+    } else {
+        BeaconStatus::Modified
+    }
+}
+
+/// Formats the verification token of the payload characters in `message`.
+fn payload_token(message: &str) -> String {
+    format!("{:08x}", crc32(remove_unnecessary_symbols(message).as_bytes()))
+}
+
+/// Encodes `secret` like `create_secret` and also returns a short verification
+/// token, a CRC-32 of the payload in hex.
+///
+/// The token can be published out of band. `verify_token` then tells whether a
+/// received message still carries the same payload. It detects stripping and
+/// accidental damage, not deliberate forgery.
+pub fn create_secret_with_token(visible: &str, secret: &str) -> (String, String) {
+    let message = create_secret(visible, secret);
+    let token = payload_token(&message);
+    (message, token)
+}
+
+/// Checks the payload of `message` against a token from `create_secret_with_token`.
+/// Edits to the visible text don't affect the result.
+pub fn verify_token(message: &str, token: &str) -> bool {
+    payload_token(message).eq_ignore_ascii_case(token.trim())
+}
+
+/// First byte of a payload with metadata. Like `BEACON_MARKER`, it never occurs
+/// in UTF-8.
+const META_MARKER: u8 = 0xFE;
+
+/// Encodes `secret` together with key-value metadata (e.g. author or subject)
+/// in the middle of `visible`.
+///
+/// The payload is the marker, the number of entries, each entry as a
+/// length-prefixed key (1 byte) and value (2 bytes, big-endian), then the
+/// secret. Returns `None` if there are more than 255 entries, a key is longer
+/// than 255 bytes or a value longer than 65535 bytes.
+pub fn create_secret_meta(visible: &str, secret: &str, meta: &[(&str, &str)]) -> Option<String> {
+    let mut payload = vec![META_MARKER, u8::try_from(meta.len()).ok()?];
+    for (key, value) in meta {
+        payload.push(u8::try_from(key.len()).ok()?);
+        payload.extend_from_slice(key.as_bytes());
+        payload.extend_from_slice(&u16::try_from(value.len()).ok()?.to_be_bytes());
+        payload.extend_from_slice(value.as_bytes());
+    }
+    payload.extend_from_slice(secret.as_bytes());
+
+    let (head, tail) = visible.split_at(midpoint(visible));
+    Some(format!("{}{}{}", head, encode_bytes(&payload), tail))
+}
+
+/// Splits `n` bytes off the front of `bytes`.
+fn take_bytes<'a>(bytes: &mut &'a [u8], n: usize) -> Option<&'a [u8]> {
+    if bytes.len() < n {
+        return None;
+    }
+    let (head, tail) = bytes.split_at(n);
+    *bytes = tail;
+    Some(head)
+}
+
+/// Decodes a message produced by `create_secret_meta` into its secret and
+/// metadata entries, in their original order.
+///
+/// Returns `None` if the payload carries no metadata or is malformed.
+pub fn extract_meta(message: &str) -> Option<(String, Vec<(String, String)>)> {
+    let payload = extract_raw(message)?;
+    let mut rest = payload.as_slice();
+    let [META_MARKER, count] = *take_bytes(&mut rest, 2)? else {
+        return None;
+    };
+
+    let mut meta = Vec::with_capacity(usize::from(count));
+    for _ in 0..count {
+        let key_len = usize::from(take_bytes(&mut rest, 1)?[0]);
+        let key = std::str::from_utf8(take_bytes(&mut rest, key_len)?).ok()?;
+        let value_len = u16::from_be_bytes(take_bytes(&mut rest, 2)?.try_into().ok()?);
+        let value = std::str::from_utf8(take_bytes(&mut rest, usize::from(value_len))?).ok()?;
+        meta.push((key.to_string(), value.to_string()));
     }
+    let secret = std::str::from_utf8(rest).ok()?;
+    Some((secret.to_string(), meta))
+}
 
+/// Appends `value` as an unsigned LEB128 varint: 7 bits per byte, least
+/// significant group first, with the high bit set on every byte but the last.
+/// Lengths below 128 take a single byte.
+fn push_varint(bytes: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
 
+/// Reads a varint written by `push_varint` from the front of `bytes` and
+/// returns it with the bytes after it. Returns `None` if the varint is cut
+/// off or doesn't fit in 32 bits.
+fn take_varint(bytes: &[u8]) -> Option<(usize, &[u8])> {
+    let mut value = 0u64;
+    for (i, &byte) in bytes.iter().enumerate().take(5) {
+        value |= u64::from(byte & 0x7F) << (7 * i);
+        if byte & 0x80 == 0 {
+            let value = u32::try_from(value).ok()?;
+            return Some((value as usize, &bytes[i + 1..]));
+        }
+    }
+    None
+}
+
+/// First byte of a payload written by `create_secret_with_footer`.
+const LENGTH_MARKER: u8 = 0xFD;
+/// Last byte of a payload written by `create_secret_with_footer`.
+const FOOTER_MARKER: u8 = 0xFC;
+
+/// Reasons `extract_secret_with_footer` can't return a secret.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FooterError {
+    /// The message carries no payload written by `create_secret_with_footer`.
+    NotFramed,
+    /// The payload is shorter than its length header says, or its footer is
+    /// missing, so the message was cut off.
+    TruncatedMessage { expected: usize, available: usize },
+    /// The payload is longer than its length header says, e.g. because other
+    /// payload characters were pasted next to it.
+    TrailingData { expected: usize, available: usize },
+    /// The payload is complete but not valid UTF-8.
+    InvalidUtf8,
+}
+
+impl std::fmt::Display for FooterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FooterError::NotFramed => write!(f, "the message has no length header"),
+            FooterError::TruncatedMessage { expected, available } => {
+                write!(f, "the message was cut off: expected {expected} payload bytes, found {available}")
+            }
+            FooterError::TrailingData { expected, available } => {
+                write!(f, "the message has extra data: expected {expected} payload bytes, found {available}")
+            }
+            FooterError::InvalidUtf8 => write!(f, "the secret is not valid UTF-8"),
+        }
+    }
+}
+
+impl std::error::Error for FooterError {}
+
+/// Encodes `secret` with a length header and a footer, so a reader can tell a
+/// complete message from one whose end was cut off, e.g. by a clipboard
+/// manager that silently truncates long text.
+pub fn create_secret_with_footer(visible: &str, secret: &str) -> String {
+    let mut payload = vec![LENGTH_MARKER];
+    push_varint(&mut payload, secret.len());
+    payload.extend_from_slice(secret.as_bytes());
+    payload.push(FOOTER_MARKER);
+
+    let (head, tail) = visible.split_at(midpoint(visible));
+    format!("{}{}{}", head, encode_bytes(&payload), tail)
+}
+
+/// Decodes a message produced by `create_secret_with_footer`, checking that
+/// the whole payload, up to and including its footer, is present.
+pub fn extract_secret_with_footer(message: &str) -> Result<String, FooterError> {
+    let filtered = remove_unnecessary_symbols(message);
+    // Only whole bytes count; a cut can leave a partial one behind.
+    let whole = filtered.len() / (8 * EXP_SIZE) * 8 * EXP_SIZE;
+    let payload = decode_bytes(&filtered[..whole]).ok_or(FooterError::NotFramed)?;
+    let Some((&LENGTH_MARKER, header)) = payload.split_first() else {
+        return Err(FooterError::NotFramed);
+    };
+    let (len, rest) = take_varint(header).ok_or(FooterError::NotFramed)?;
+
+    // `len` can be as large as `u32::MAX`, which overflows on 32-bit targets.
+    let expected = len.checked_add(1).ok_or(FooterError::NotFramed)?;
+    if rest.len() > expected {
+        return Err(FooterError::TrailingData { expected, available: rest.len() });
+    }
+    if rest.len() < expected || rest.last() != Some(&FOOTER_MARKER) {
+        return Err(FooterError::TruncatedMessage { expected, available: rest.len() });
+    }
+    String::from_utf8(rest[..len].to_vec()).map_err(|_| FooterError::InvalidUtf8)
+}
+
+/// Returns `true` if `message` starts or ends with a payload character, where it
+/// could be clipped by tools that trim the edges of a text.
+pub fn payload_at_edge(message: &str) -> bool {
+    let is_payload = |c: char| Alphabet::DEFAULT.contains(c);
+    message.chars().next().is_some_and(is_payload) || message.chars().next_back().is_some_and(is_payload)
+}
+
+/// Location of one contiguous run of payload characters within a message.
+///
+/// Offsets are half-open ranges: `byte_start..byte_end` indexes the `str`, and
+/// `char_start..char_end` counts Unicode scalar values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub byte_start: usize,
+    pub byte_end: usize,
+    pub char_start: usize,
+    pub char_end: usize,
+}
+
+/// Returns every contiguous run of payload characters in `message`, in order.
+pub fn hidden_spans(message: &str) -> Vec<Span> {
+    let mut spans: Vec<Span> = Vec::new();
+    let mut in_run = false;
+    for (char_index, (byte_index, c)) in message.char_indices().enumerate() {
+        if !Alphabet::DEFAULT.contains(c) {
+            in_run = false;
+            continue;
+        }
+        let byte_end = byte_index + c.len_utf8();
+        match spans.last_mut() {
+            Some(span) if in_run => {
+                span.byte_end = byte_end;
+                span.char_end = char_index + 1;
+            }
+            _ => spans.push(Span {
+                byte_start: byte_index,
+                byte_end,
+                char_start: char_index,
+                char_end: char_index + 1,
+            }),
+        }
+        in_run = true;
+    }
+    spans
+}
+
+/// Decodes every separate payload in `message`, treating each contiguous run of
+/// payload characters as its own secret.
+///
+/// Secrets are returned in ascending byte offset of their runs, i.e. left to
+/// right in the message, regardless of their content. Runs that don't decode
+/// (for example the fragments of a spread payload) are skipped.
+pub fn extract_all(message: &str) -> Vec<String> {
+    hidden_spans(message)
+        .into_iter()
+        .filter_map(|span| decode(&message[span.byte_start..span.byte_end]))
+        .collect()
+}
+
+/// Encodes `redundancy` copies of `secret` at evenly spaced positions of
+/// `visible`, so it can be recovered even if a lossy channel damages some copies.
+///
+/// Each copy carries a CRC-32 of the secret, which `extract_secret_redundant`
+/// uses to tell intact copies from damaged ones. Copies must be separated by
+/// visible text, so `visible` needs more than `redundancy` characters. Returns
+/// `None` if it is shorter or `redundancy` is zero.
+pub fn create_secret_redundant(visible: &str, secret: &str, redundancy: u8) -> Option<String> {
+    let copies = usize::from(redundancy);
+    let boundaries: Vec<usize> = visible.char_indices().map(|(i, _)| i).collect();
+    if copies == 0 || boundaries.len() <= copies {
+        return None;
+    }
+
+    let mut framed = secret.as_bytes().to_vec();
+    framed.extend_from_slice(&crc32(secret.as_bytes()).to_be_bytes());
+    let payload = encode_bytes(&framed);
+
+    let mut result = String::with_capacity(visible.len() + copies * payload.len());
+    let mut start = 0;
+    for copy in 1..=copies {
+        let position = boundaries[copy * boundaries.len() / (copies + 1)];
+        result.push_str(&visible[start..position]);
+        result.push_str(&payload);
+        start = position;
+    }
+    result.push_str(&visible[start..]);
+    Some(result)
+}
+
+/// Decodes a message produced by `create_secret_redundant`, returning the first
+/// copy whose checksum matches, or `None` if every copy is damaged.
+pub fn extract_secret_redundant(message: &str) -> Option<String> {
+    hidden_spans(message).into_iter().find_map(|span| {
+        let mut framed = decode_bytes(&message[span.byte_start..span.byte_end])?;
+        let checksum = framed.split_off(framed.len().checked_sub(4)?);
+        if crc32(&framed).to_be_bytes()[..] != checksum[..] {
+            return None;
+        }
+        String::from_utf8(framed).ok()
+    })
+}
+
+/// Replaces each contiguous run of payload characters in `message` with a single
+/// `placeholder`, e.g. `⟦hidden⟧`.
+///
+/// The result shows where the payload sits without revealing it or its exact
+/// length, which makes it safe for screenshots and documentation.
+pub fn redact_payload(message: &str, placeholder: &str) -> String {
+    let mut result = String::with_capacity(message.len());
+    let mut in_run = false;
+    for c in message.chars() {
+        if Alphabet::DEFAULT.contains(c) {
+            if !in_run {
+                result.push_str(placeholder);
+                in_run = true;
+            }
+        } else {
+            result.push(c);
+            in_run = false;
+        }
+    }
+    result
+}
+
+/// Removes HTML tags and comments, keeping only the text content.
+fn strip_tags(html: &str) -> String {
+    let mut result = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        result.push_str(&rest[..start]);
+        let tag = &rest[start..];
+        let end = if tag.starts_with("<!--") {
+            tag.find("-->").map(|i| i + 3)
+        } else {
+            tag.find('>').map(|i| i + 1)
+        };
+        // An unterminated tag swallows the rest of the input, like in a browser.
+        rest = &tag[end.unwrap_or(tag.len())..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Decodes a single entity body (the part between `&` and `;`).
+fn decode_entity(entity: &str) -> Option<char> {
+    if let Some(hex) = entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X")) {
+        return u32::from_str_radix(hex, 16).ok().and_then(char::from_u32);
+    }
+    if let Some(dec) = entity.strip_prefix('#') {
+        return dec.parse().ok().and_then(char::from_u32);
+    }
+    match entity {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some('\u{00A0}'),
+        "zwnj" => Some('\u{200C}'),
+        "zwj" => Some('\u{200D}'),
+        _ => None,
+    }
+}
+
+/// Decodes HTML character references. Unknown or malformed entities are kept as-is.
+pub fn decode_entities(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest[1..]
+            .find(';')
+            .and_then(|end| decode_entity(&rest[1..end + 1]).map(|c| (c, end + 2)));
+        match decoded {
+            Some((c, len)) => {
+                result.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Returns the contents of every `<!-- ... -->` comment in `html`.
+fn html_comments(html: &str) -> Vec<&str> {
+    let mut comments = Vec::new();
+    let mut rest = html;
+    while let Some(start) = rest.find("<!--") {
+        let body = &rest[start + 4..];
+        let Some(end) = body.find("-->") else { break };
+        comments.push(&body[..end]);
+        rest = &body[end + 3..];
+    }
+    comments
+}
+
+/// Finds and decodes a secret message from an HTML fragment.
+///
+/// Tags and comments are removed before extraction, so zero-width characters
+/// inside markup (e.g. in attribute values) are not treated as payload. Character
+/// references such as `&zwnj;` or `&#x200D;` are decoded afterwards. If the text
+/// carries no secret, comments are searched instead, which recovers payloads
+/// written by `create_secret_html_comment`.
+pub fn extract_secret_from_html(html: &str) -> Option<String> {
+    match try_extract_secret_from_html(html) {
+        Ok(secret) => Some(secret),
+        Err(ExtractError::NoPayload) => Some(String::new()),
+        Err(_) => None,
+    }
+}
+
+/// Like `extract_secret_from_html`, but reports why no secret was found.
+pub fn try_extract_secret_from_html(html: &str) -> Result<String, ExtractError> {
+    try_extract_secret(&decode_entities(&strip_tags(html))).or_else(|error| {
+        html_comments(html)
+            .into_iter()
+            .find_map(|comment| try_extract_secret(comment).ok())
+            .ok_or(error)
+    })
+}
+
+/// Encodes `secret` into an HTML comment appended to `visible`, so the payload
+/// stays in the page source but never reaches the rendered DOM or text copied
+/// from it. `visible` is taken as HTML and kept as it is.
+pub fn create_secret_html_comment(visible: &str, secret: &str) -> String {
+    format!("{}<!--{}-->", visible, encode(secret))
+}
+
+/// Decodes `%XX` escapes. Malformed escapes are kept as-is, and byte
+/// sequences that aren't valid UTF-8 become U+FFFD.
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| text.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                result.push(byte);
+                i += 3;
+            }
+            None => {
+                result.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&result).into_owned()
+}
+
+/// Percent-decodes `message` if it contains escaped payload characters.
+///
+/// Zero-width characters in URLs arrive as `%E2%80%8C` and `%E2%80%8D`. Input
+/// without either is returned unchanged, so a literal `%` in an ordinary
+/// message is left alone.
+pub fn decode_url_escapes(message: &str) -> String {
+    let upper = message.to_ascii_uppercase();
+    if upper.contains("%E2%80%8C") || upper.contains("%E2%80%8D") {
+        percent_decode(message)
+    } else {
+        message.to_string()
+    }
+}
+
+/// Finds and decodes a secret message that was passed through a URL, see
+/// `decode_url_escapes`.
+pub fn extract_secret_from_url(message: &str) -> Option<String> {
+    extract_secret(&decode_url_escapes(message))
+}
+
+/// String literal syntax used by `to_escaped` and `from_escaped`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EscapeStyle {
+    /// `"\u{200c}"`
+    Rust,
+    /// `"\u200c"`, with surrogate pairs above U+FFFF.
+    Json,
+    /// `"\u200c"`, or `"\U000e0020"` above U+FFFF.
+    Python,
+}
+
+/// Writes `c` as a code point escape in the given style.
+fn push_code_point(result: &mut String, c: char, style: EscapeStyle) {
+    match style {
+        EscapeStyle::Rust => result.push_str(&format!("\\u{{{:x}}}", c as u32)),
+        EscapeStyle::Json => {
+            for unit in c.encode_utf16(&mut [0; 2]) {
+                result.push_str(&format!("\\u{unit:04x}"));
+            }
+        }
+        EscapeStyle::Python if c as u32 > 0xFFFF => result.push_str(&format!("\\U{:08x}", c as u32)),
+        EscapeStyle::Python => result.push_str(&format!("\\u{:04x}", c as u32)),
+    }
+}
+
+/// Quotes `message` as a string literal, escaping payload and other invisible
+/// characters, so it survives editors and source control when embedded in code
+/// or configuration. Visible characters are kept as they are.
+pub fn to_escaped(message: &str, style: EscapeStyle) -> String {
+    let mut result = String::with_capacity(message.len() + 2);
+    result.push('"');
+    for c in message.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if c.is_control() || is_invisible(c) => push_code_point(&mut result, c, style),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+/// Reads `n` hexadecimal digits.
+fn take_hex(chars: &mut std::str::Chars<'_>, n: usize) -> Option<u32> {
+    let digits: String = chars.take(n).collect();
+    if digits.len() != n {
+        return None;
+    }
+    u32::from_str_radix(&digits, 16).ok()
+}
+
+/// Parses a string literal in the given style, e.g. one written by `to_escaped`.
+///
+/// Returns `None` if `literal` isn't a double-quoted literal or contains an
+/// escape the style doesn't support.
+pub fn from_escaped(literal: &str, style: EscapeStyle) -> Option<String> {
+    let body = literal.trim().strip_prefix('"')?.strip_suffix('"')?;
+    let mut result = String::with_capacity(body.len());
+    let mut chars = body.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return None,
+            '\\' => {}
+            c => {
+                result.push(c);
+                continue;
+            }
+        }
+        let c = match (chars.next()?, style) {
+            ('"', _) => '"',
+            ('\\', _) => '\\',
+            ('n', _) => '\n',
+            ('r', _) => '\r',
+            ('t', _) => '\t',
+            ('0', EscapeStyle::Rust | EscapeStyle::Python) => '\0',
+            ('\'', EscapeStyle::Rust | EscapeStyle::Python) => '\'',
+            ('/', EscapeStyle::Json) => '/',
+            ('b', EscapeStyle::Json) => '\u{8}',
+            ('f', EscapeStyle::Json) => '\u{c}',
+            ('x', EscapeStyle::Rust) => char::from_u32(take_hex(&mut chars, 2).filter(|&x| x <= 0x7F)?)?,
+            ('x', EscapeStyle::Python) => char::from_u32(take_hex(&mut chars, 2)?)?,
+            ('u', EscapeStyle::Rust) => {
+                if chars.next()? != '{' {
+                    return None;
+                }
+                let digits: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                if digits.is_empty() || digits.len() > 6 {
+                    return None;
+                }
+                char::from_u32(u32::from_str_radix(&digits, 16).ok()?)?
+            }
+            ('u', EscapeStyle::Json) => {
+                let high = take_hex(&mut chars, 4)?;
+                let mut units = vec![high as u16];
+                if (0xD800..0xDC00).contains(&high) {
+                    if chars.next()? != '\\' || chars.next()? != 'u' {
+                        return None;
+                    }
+                    units.push(take_hex(&mut chars, 4)? as u16);
+                }
+                char::decode_utf16(units).next()?.ok()?
+            }
+            ('u', EscapeStyle::Python) => char::from_u32(take_hex(&mut chars, 4)?)?,
+            ('U', EscapeStyle::Python) => char::from_u32(take_hex(&mut chars, 8)?)?,
+            _ => return None,
+        };
+        result.push(c);
+    }
+    Some(result)
+}
+
+/// Encodes `secret` right after the first occurrence of `needle` in `visible`,
+/// validating the inputs like `create_secret_with`.
+///
+/// A needle at the very end of `visible` leaves the payload at the edge, where
+/// it may be trimmed (see `payload_at_edge`).
+pub fn create_secret_after(visible: &str, needle: &str, secret: &str) -> Result<String, EncodeError> {
+    check_inputs(visible, secret, &Alphabet::DEFAULT)?;
+    // An empty needle would match at the very start of the carrier.
+    if needle.is_empty() {
+        return Err(EncodeError::EmptyNeedle);
+    }
+    let start = visible.find(needle).ok_or(EncodeError::NeedleNotFound)?;
+    // `find` returns a char boundary and `needle` is whole characters, so the
+    // end is one too.
+    let (head, tail) = visible.split_at(start + needle.len());
+    Ok(format!("{}{}{}", head, encode(secret), tail))
+}
+
+/// Fills `template` by replacing the first occurrence of `placeholder` with the
+/// encoded `secret`, e.g. for a fixed email signature.
+///
+/// Returns `None` if `template` doesn't contain `placeholder` or it is empty.
+pub fn create_secret_templated(template: &str, placeholder: &str, secret: &str) -> Option<String> {
+    if placeholder.is_empty() {
+        return None;
+    }
+    let (head, tail) = template.split_once(placeholder)?;
+    Some(format!("{}{}{}", head, encode(secret), tail))
+}
+
+/// Returns `true` if `c` usually starts an emoji: pictographs, dingbats and
+/// symbols, regional indicators and the keycap bases.
+fn starts_emoji(c: char) -> bool {
+    matches!(c,
+        '\u{1F000}'..='\u{1FAFF}'
+        | '\u{2300}'..='\u{23FF}'
+        | '\u{2600}'..='\u{27BF}'
+        | '\u{2B00}'..='\u{2BFF}'
+        | '\u{2190}'..='\u{21FF}'
+        | '\u{00A9}' | '\u{00AE}' | '\u{203C}' | '\u{2049}' | '\u{2122}' | '\u{2139}'
+        | '\u{3030}' | '\u{303D}' | '\u{3297}' | '\u{3299}'
+        | '0'..='9' | '#' | '*')
+}
+
+/// Hint for `create_secret_for_script` about how the carrier's script is shaped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Script {
+    /// Letters never join or combine across code points, e.g. Latin, Cyrillic
+    /// or Greek. The payload goes in the middle, as with `create_secret`.
+    Default,
+    /// Consonants combine with vowel signs and viramas into conjuncts, e.g.
+    /// Devanagari, Bengali or Tamil. The payload goes at the start of a word.
+    Indic,
+    /// Letters join cursively, e.g. Arabic or Syriac. Both payload characters
+    /// change how an adjacent letter joins, so the payload only goes between
+    /// two characters that aren't letters.
+    Joining,
+}
+
+/// Returns `true` if the payload can go at byte `idx` of `s` without changing
+/// how text written in `script` is shaped.
+fn safe_for_script(s: &str, idx: usize, script: Script) -> bool {
+    let before = s[..idx].chars().next_back();
+    let after = s[idx..].chars().next();
+    match script {
+        Script::Default => true,
+        Script::Indic => before.is_some_and(char::is_whitespace) || (after.is_none() && before.is_some()),
+        Script::Joining => {
+            let letter = |c: Option<char>| c.is_some_and(char::is_alphanumeric);
+            (before.is_some() || after.is_some()) && !letter(before) && !letter(after)
+        }
+    }
+}
+
+/// Like `create_secret`, but keeps the payload away from characters that shape
+/// together in the given script, which would render differently even though
+/// the code points are unchanged: e.g. a payload between a Devanagari
+/// consonant and its vowel sign breaks the two apart.
+///
+/// The payload goes at the safe position closest to the middle. Returns `None`
+/// if the carrier has none, e.g. an Arabic carrier without punctuation.
+pub fn create_secret_for_script(normal_str: &str, secret: &str, script: Script) -> Option<String> {
+    if script == Script::Default {
+        return Some(create_secret(normal_str, secret));
+    }
+    let mid = normal_str.len() / 2;
+    let position = normal_str
+        .grapheme_indices(true)
+        .map(|(idx, _)| idx)
+        .chain(std::iter::once(normal_str.len()))
+        .filter(|&idx| safe_for_script(normal_str, idx, script))
+        .min_by_key(|&idx| idx.abs_diff(mid))?;
+    let (head, tail) = normal_str.split_at(position);
+    Some(format!("{}{}{}", head, encode(secret), tail))
+}
+
+/// Appends the encoded `secret` to a single emoji, so the message still looks
+/// like that one emoji.
+///
+/// Both payload characters extend the preceding grapheme, so the result stays a
+/// single grapheme and renders unchanged. Emoji that are themselves ZWJ
+/// sequences (e.g. 👨‍👩‍👧) contain U+200D and can't carry a payload. Returns
+/// `None` for those and for anything that isn't exactly one emoji. Text typed
+/// right after the result may join with a trailing U+200D, so put any further
+/// text before the emoji.
+pub fn create_secret_emoji(emoji: &str, secret: &str) -> Option<String> {
+    let mut graphemes = emoji.graphemes(true);
+    let grapheme = graphemes.next()?;
+    let is_emoji = match grapheme.chars().next() {
+        // ASCII bases are only emoji as part of a keycap sequence.
+        Some(c) if c.is_ascii() => starts_emoji(c) && grapheme.ends_with('\u{20E3}'),
+        Some(c) => starts_emoji(c),
+        None => false,
+    };
+    if graphemes.next().is_some() || !is_emoji || grapheme.chars().any(|c| Alphabet::DEFAULT.contains(c)) {
+        return None;
+    }
+    Some(format!("{}{}", emoji, encode(secret)))
+}
+
+/// Encodes at most `max_bytes` bytes of `secret` into `visible`, as `create_secret` would.
+///
+/// A longer secret is cut at the last character boundary that fits, so the
+/// result always decodes to valid UTF-8. The flag is `true` if anything was cut.
+pub fn create_secret_capped(visible: &str, secret: &str, max_bytes: usize) -> (String, bool) {
+    if secret.len() <= max_bytes {
+        return (create_secret(visible, secret), false);
+    }
+    let end = snap_to_char_boundary(secret, max_bytes);
+    (create_secret(visible, &secret[..end]), true)
+}
+
+/// Encodes each of `secrets` into its own copy of `visible`, as `create_secret` would.
+///
+/// The insertion point is computed once and shared by all outputs.
+pub fn create_secret_batch(visible: &str, secrets: &[&str]) -> Vec<String> {
+    let (head, tail) = visible.split_at(midpoint(visible));
+    secrets
+        .iter()
+        .map(|secret| {
+            let mut result = String::with_capacity(visible.len() + secret.len() * 8 * EXP_SIZE);
+            result.push_str(head);
+            result.extend(payload_chars(secret));
+            result.push_str(tail);
+            result
+        })
+        .collect()
+}
+
+/// Controls how `create_secret_spread` distributes the payload over the carrier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpreadDensity {
+    /// Spaces the payload characters as evenly as possible across the whole carrier.
+    Even,
+    /// Places one payload character after every `n` visible characters. Whatever
+    /// doesn't fit is appended after the carrier, and a gap of 0 keeps the whole
+    /// payload in front of it.
+    FixedGap(usize),
+}
+
+/// Iterates over the zero-width characters encoding `secret`, one bit at a time.
+fn payload_chars(secret: &str) -> impl Iterator<Item = &'static str> + '_ {
+    secret
+        .bytes()
+        .flat_map(|byte| (0..8).map(move |i| if (byte >> i) & 1 == 1 { I_1 } else { I_0 }))
+}
+
+/// Encodes a secret message, spreading its zero-width characters across the
+/// visible text instead of inserting them in one run.
+///
+/// Extraction is unaffected by the layout: `extract_secret` collects invisible
+/// characters wherever they are.
+///
+/// The output is built in a single pass into a pre-sized buffer, so the cost is
+/// linear in the size of the carrier plus the payload (see `benches/spread.rs`).
+pub fn create_secret_spread(normal_str: &str, secret: &str, density: SpreadDensity) -> String {
+    let visible_count = normal_str.chars().count();
+    let payload_count = secret.len() * 8;
+    // Number of visible characters that precede the k-th payload character.
+    let position = |k: usize| match density {
+        SpreadDensity::Even => (k + 1) * visible_count / (payload_count + 1),
+        SpreadDensity::FixedGap(gap) => (k + 1).saturating_mul(gap),
+    };
+
+    let mut result = String::with_capacity(normal_str.len() + payload_count * EXP_SIZE);
+    let mut visible = normal_str.chars();
+    let mut emitted = 0;
+    for (k, symbol) in payload_chars(secret).enumerate() {
+        while emitted < position(k) {
+            match visible.next() {
+                Some(c) => result.push(c),
+                None => break,
+            }
+            emitted += 1;
+        }
+        result.push_str(symbol);
+    }
+    result.extend(visible);
+    result
+}
+
+/// SplitMix64, a small deterministic generator. It only picks insertion points
+/// and must never be used for anything that needs to be unpredictable.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+/// Encodes a secret message as up to one run per secret byte, placed at
+/// distinct positions inside the visible text chosen by `seed`.
+///
+/// The same seed always gives the same layout, which `verify_placement` can
+/// check later, e.g. to trace which copy of a document leaked. Extraction
+/// doesn't need the seed. If the secret has more bytes than the carrier has
+/// positions between its characters, every position gets a run.
+pub fn create_secret_seeded(normal_str: &str, secret: &str, seed: u64) -> String {
+    // Byte offsets between two visible characters, so no run sits at an edge.
+    let mut positions: Vec<usize> = normal_str.char_indices().skip(1).map(|(i, _)| i).collect();
+    if positions.is_empty() {
+        positions.push(midpoint(normal_str));
+    }
+    let runs = secret.len().min(positions.len());
+
+    // Partial Fisher-Yates shuffle: the first `runs` positions are the chosen ones.
+    let mut rng = SplitMix64(seed);
+    for i in 0..runs {
+        let j = i + (rng.next() % (positions.len() - i) as u64) as usize;
+        positions.swap(i, j);
+    }
+    let mut chosen = positions[..runs].to_vec();
+    chosen.sort_unstable();
+
+    let payload = encode(secret);
+    let mut result = String::with_capacity(normal_str.len() + payload.len());
+    let mut start = 0;
+    for (run, &position) in chosen.iter().enumerate() {
+        // Split the payload into runs of whole encoded bytes, as even as possible.
+        let from = run * secret.len() / runs * 8 * EXP_SIZE;
+        let to = (run + 1) * secret.len() / runs * 8 * EXP_SIZE;
+        result.push_str(&normal_str[start..position]);
+        result.push_str(&payload[from..to]);
+        start = position;
+    }
+    result.push_str(&normal_str[start..]);
+    result
+}
+
+/// Returns `true` if `message` carries a payload laid out exactly as
+/// `create_secret_seeded` would with `seed`. A message without a payload
+/// never verifies.
+pub fn verify_placement(message: &str, seed: u64) -> bool {
+    try_extract_secret(message).is_ok_and(|secret| create_secret_seeded(&recover_carrier(message), &secret, seed) == message)
+}
+
+/// Estimates how many bits of placement freedom `create_secret_seeded` has for a
+/// carrier of `visible_len` characters and a secret of `payload_len` bytes, i.e.
+/// the base-2 logarithm of the number of distinct layouts.
+///
+/// Each layout picks one position per run, so the count is a binomial
+/// coefficient: it grows with the carrier and drops to zero bits once the runs
+/// fill every position.
+pub fn placement_entropy(visible_len: usize, payload_len: usize) -> f64 {
+    let positions = visible_len.saturating_sub(1).max(1);
+    let runs = payload_len.min(positions);
+    // C(n, k) = C(n, n - k); the shorter product has less rounding error.
+    let k = runs.min(positions - runs);
+    (0..k).map(|i| ((positions - i) as f64 / (i + 1) as f64).log2()).sum()
+}
+
+/// Invisible character mixed into the payload by `create_secret_noisy`. It is
+/// the word joiner, which isn't part of the alphabet, so extraction skips it.
+pub const NOISE_CHAR: char = '\u{2060}';
+
+/// How much noise `create_secret_noisy` mixes into the payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoiseLevel {
+    /// No noise; the message is the same as with `create_secret`.
+    None,
+    /// Up to 2 noise characters per secret byte.
+    Low,
+    /// Up to 8 noise characters per secret byte, doubling the payload at most.
+    High,
+}
+
+impl NoiseLevel {
+    fn max_per_byte(self) -> usize {
+        match self {
+            NoiseLevel::None => 0,
+            NoiseLevel::Low => 2,
+            NoiseLevel::High => 8,
+        }
+    }
+}
+
+/// Like `create_secret`, but mixes a number of `NOISE_CHAR`s chosen by `seed`
+/// into the payload, so the count of invisible characters varies between
+/// messages with the same secret and doesn't give the secret's length away.
+///
+/// Extraction with `extract_secret` is unaffected. Functions that look at
+/// contiguous runs of payload characters, such as `extract_all` or
+/// `hidden_spans`, see the noise as a break in the run.
+pub fn create_secret_noisy(normal_str: &str, secret: &str, noise: NoiseLevel, seed: u64) -> String {
+    let mut rng = SplitMix64(seed);
+    let max = (secret.len() * noise.max_per_byte()) as u64;
+    let payload: Vec<char> = encode(secret).chars().collect();
+    // noise_at[i] noise characters go in front of the i-th payload character.
+    let slots = payload.len() + 1;
+    let mut noise_at = vec![0; slots];
+    for _ in 0..rng.next() % (max + 1) {
+        noise_at[(rng.next() % slots as u64) as usize] += 1;
+    }
+
+    let mut hidden = String::with_capacity((payload.len() + max as usize) * EXP_SIZE);
+    for (i, &count) in noise_at.iter().enumerate() {
+        hidden.extend(std::iter::repeat_n(NOISE_CHAR, count));
+        hidden.extend(payload.get(i));
+    }
+    let (head, tail) = normal_str.split_at(midpoint(normal_str));
+    format!("{}{}{}", head, hidden, tail)
+}
+
+/// High-level function to find and decode a secret message from a larger string.
+///
+/// Extraction works on the logical order of code points, i.e. the order in which
+/// they are stored, never on how they are displayed. Right-to-left text and bidi
+/// controls such as U+200E, U+200F, U+202A–U+202E and U+2066–U+2069 change only
+/// the display order, so they don't affect the recovered secret.
+///
+/// Everything outside the alphabet is ignored, so changes to ASCII whitespace,
+/// such as collapsed runs of spaces or trimmed lines, never affect extraction.
+pub fn extract_secret(message: &str) -> Option<String> {
+    let filtered = remove_unnecessary_symbols(message);
+    decode(&filtered)
+}
+
+/// Returns the payload bytes of `message` as they were encoded, for callers
+/// that process them further themselves.
+///
+/// The payload characters of the whole message are collected, in order and
+/// across separate runs, and read back eight at a time, least significant bit
+/// first. That is all: the bytes aren't checked to be UTF-8, a payload of NUL
+/// bytes only is returned as it is, and markers written by other encoders,
+/// such as the length header of `create_secret_with_footer` or a gzip header,
+/// are left in place. The default format has no framing of its own to remove.
+///
+/// Returns `None` if the payload isn't a whole number of bytes. A message
+/// without a payload gives an empty vector.
+pub fn extract_raw(message: &str) -> Option<Vec<u8>> {
+    decode_bytes(&remove_unnecessary_symbols(message))
+}
+
+/// Reasons `try_extract_secret` can't return a secret.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtractError {
+    /// The message carries no payload characters at all.
+    NoPayload,
+    /// The payload ends `bits` bits into a byte, so characters were lost.
+    IncompleteByte { bits: usize },
+    /// The payload consists of NUL bytes only. A secret of NULs gives one, but
+    /// so does a payload that lost its `I_1` characters.
+    OnlyNulBytes,
+    /// The payload bytes are not valid UTF-8.
+    InvalidUtf8,
+}
+
+impl std::fmt::Display for ExtractError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExtractError::NoPayload => write!(f, "the message has no payload"),
+            ExtractError::IncompleteByte { bits } => write!(f, "the payload ends {bits} bits into a byte"),
+            ExtractError::OnlyNulBytes => write!(f, "the payload contains only NUL bytes"),
+            ExtractError::InvalidUtf8 => write!(f, "the secret is not valid UTF-8"),
+        }
+    }
+}
+
+impl std::error::Error for ExtractError {}
+
+/// Like `extract_secret`, but tells a message without a payload apart from one
+/// whose payload can't be decoded, and reports why.
+pub fn try_extract_secret(message: &str) -> Result<String, ExtractError> {
+    let filtered = remove_unnecessary_symbols(message);
+    if filtered.is_empty() {
+        return Err(ExtractError::NoPayload);
+    }
+    let bits = filtered.len() / EXP_SIZE;
+    if !bits.is_multiple_of(8) {
+        return Err(ExtractError::IncompleteByte { bits: bits % 8 });
+    }
+    if !filtered.contains(I_1) {
+        return Err(ExtractError::OnlyNulBytes);
+    }
+    let bytes = decode_bytes(&filtered).ok_or(ExtractError::IncompleteByte { bits: 0 })?;
+    String::from_utf8(bytes).map_err(|_| ExtractError::InvalidUtf8)
+}
+
+/// Returns whether `a` and `b` carry the same secret, e.g. to deduplicate
+/// messages, or `None` if either has no payload or one that can't be decoded.
+pub fn same_secret(a: &str, b: &str) -> Option<bool> {
+    Some(try_extract_secret(a).ok()? == try_extract_secret(b).ok()?)
+}
+
+/// Returns the secret of `candidate` paired with `index`, if it carries a
+/// non-empty one.
+fn indexed_secret((index, candidate): (usize, &&str)) -> Option<(usize, String)> {
+    extract_secret(candidate).filter(|secret| !secret.is_empty()).map(|secret| (index, secret))
+}
+
+/// Returns the index and secret of the first of `candidates` that carries a
+/// non-empty secret, e.g. when scanning log lines or clipboard entries.
+pub fn extract_first(candidates: &[&str]) -> Option<(usize, String)> {
+    candidates.iter().enumerate().find_map(indexed_secret)
+}
+
+/// Like `extract_first`, but returns every candidate that carries a secret, in order.
+pub fn extract_indexed(candidates: &[&str]) -> Vec<(usize, String)> {
+    candidates.iter().enumerate().filter_map(indexed_secret).collect()
+}
+
+/// Magic bytes at the start of every gzip stream.
+#[cfg(feature = "gzip")]
+const GZIP_MAGIC: [u8; 2] = [0x1F, 0x8B];
+
+/// Like `extract_secret`, but inflates payloads that are gzip streams, as some
+/// other zero-width tools write them without any flag.
+///
+/// This can't misread a plain secret: `0x8B` is a UTF-8 continuation byte, so no
+/// UTF-8 text starts with the gzip magic. For the same reason, payloads with the
+/// magic that fail to inflate are rejected rather than read as text. Streams
+/// that inflate to more than `MAX_SECRET_LEN` bytes are rejected too, so a small
+/// message can't exhaust memory.
+#[cfg(feature = "gzip")]
+pub fn extract_secret_gzip(message: &str) -> Option<String> {
+    use std::io::Read;
+
+    let bytes = extract_raw(message)?;
+    if bytes.starts_with(&GZIP_MAGIC) {
+        let mut inflated = Vec::new();
+        let limit = MAX_SECRET_LEN as u64 + 1;
+        flate2::read::GzDecoder::new(&bytes[..]).take(limit).read_to_end(&mut inflated).ok()?;
+        if inflated.len() > MAX_SECRET_LEN {
+            return None;
+        }
+        return String::from_utf8(inflated).ok();
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// Counters collected by `extract_with_metrics`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ExtractMetrics {
+    /// Payload characters found, i.e. bits.
+    pub alphabet_chars: usize,
+    /// Other invisible characters that were ignored, e.g. U+200B or U+FEFF.
+    pub skipped_zero_width: usize,
+    /// Whole bytes carried by the payload characters.
+    pub payload_bytes: usize,
+}
+
+/// Like `extract_secret`, but also reports what was found along the way, for
+/// services that want to monitor the documents they process.
+pub fn extract_with_metrics(message: &str) -> (Option<String>, ExtractMetrics) {
+    let mut metrics = ExtractMetrics::default();
+    for c in message.chars() {
+        if Alphabet::DEFAULT.contains(c) {
+            metrics.alphabet_chars += 1;
+        } else if is_invisible(c) {
+            metrics.skipped_zero_width += 1;
+        }
+    }
+    metrics.payload_bytes = metrics.alphabet_chars / 8;
+    (extract_secret(message), metrics)
+}
+
+/// Zero-width code points that are not part of the encoding alphabet and rarely
+/// appear in ordinary text.
+const UNCOMMON_ZERO_WIDTH: [char; 8] = [
+    '\u{200B}', '\u{2060}', '\u{2061}', '\u{2062}', '\u{2063}', '\u{2064}', '\u{FEFF}', '\u{180E}',
+];
+
+/// Heuristic assessment of how conspicuous a combined message is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetectabilityReport {
+    /// Overall score, from 0 (inconspicuous) to 100 (obvious).
+    pub score: u8,
+    /// Number of invisible characters per visible character.
+    pub invisible_ratio: f64,
+    /// Whether all invisible characters sit in one contiguous run.
+    pub contiguous: bool,
+    /// Zero-width code points outside the encoding alphabet, in order of first appearance.
+    pub uncommon_code_points: Vec<char>,
+}
+
+/// Estimates how easily the hidden payload in `message` could be noticed.
+///
+/// The score combines the ratio of invisible to visible characters (up to 60
+/// points, maxing out at one invisible per visible character), a single
+/// contiguous payload run (25 points) and the presence of uncommon zero-width
+/// code points (15 points). Longer carriers and spread payloads score lower.
+pub fn detectability(message: &str) -> DetectabilityReport {
+    let mut visible = 0usize;
+    let mut invisible = 0usize;
+    let mut runs = 0usize;
+    let mut in_run = false;
+    let mut uncommon_code_points = Vec::new();
+
+    for c in message.chars() {
+        let is_alphabet = Alphabet::DEFAULT.contains(c);
+        let is_uncommon = UNCOMMON_ZERO_WIDTH.contains(&c);
+        if is_uncommon && !uncommon_code_points.contains(&c) {
+            uncommon_code_points.push(c);
+        }
+        if is_alphabet || is_uncommon {
+            invisible += 1;
+            if !in_run {
+                runs += 1;
+                in_run = true;
+            }
+        } else {
+            visible += 1;
+            in_run = false;
+        }
+    }
+
+    let invisible_ratio = if visible == 0 {
+        if invisible == 0 { 0.0 } else { f64::INFINITY }
+    } else {
+        invisible as f64 / visible as f64
+    };
+    let contiguous = runs == 1;
+
+    let mut score = invisible_ratio.min(1.0) * 60.0;
+    if contiguous {
+        score += 25.0;
+    }
+    if !uncommon_code_points.is_empty() {
+        score += 15.0;
+    }
+
+    DetectabilityReport {
+        score: score.round() as u8,
+        invisible_ratio,
+        contiguous,
+        uncommon_code_points,
+    }
+}
+
+/// A pair of invisible characters representing the `0` and `1` bits of a payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Alphabet {
+    /// Character encoding a `0` bit.
+    pub zero: char,
+    /// Character encoding a `1` bit.
+    pub one: char,
+}
+
+/// Reasons an `Alphabet` is unusable, as reported by `validate_alphabet`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlphabetError {
+    /// Both bits use the same character, so they can't be told apart.
+    Identical(char),
+    /// The character is printable or whitespace and appears in ordinary text,
+    /// which would make every document look like it carries a payload.
+    CommonCharacter(char),
+    /// The character is not a known invisible character and would show up in
+    /// the message.
+    NotInvisible(char),
+}
+
+impl std::fmt::Display for AlphabetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AlphabetError::Identical(c) => write!(f, "both bits use the same character U+{:04X}", *c as u32),
+            AlphabetError::CommonCharacter(c) => {
+                write!(f, "U+{:04X} is a common printable character", *c as u32)
+            }
+            AlphabetError::NotInvisible(c) => write!(f, "U+{:04X} is not an invisible character", *c as u32),
+        }
+    }
+}
+
+impl std::error::Error for AlphabetError {}
+
+/// Returns `true` for characters known to be invisible and zero-width.
+fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{034F}'
+            | '\u{180E}'
+            | '\u{200B}'..='\u{200D}'
+            | '\u{2060}'..='\u{2064}'
+            | '\u{FEFF}'
+            | '\u{E0001}'
+            | '\u{E0020}'..='\u{E007F}'
+    )
+}
+
+/// Returns `true` for blank characters that are easy to mistake for zero-width
+/// ones but take up space when rendered: Unicode spaces other than the ASCII
+/// space, Hangul fillers and the blank Braille pattern.
+fn is_wide_blank(c: char) -> bool {
+    matches!(
+        c,
+        '\u{00A0}'
+            | '\u{115F}'
+            | '\u{1160}'
+            | '\u{2000}'..='\u{200A}'
+            | '\u{202F}'
+            | '\u{205F}'
+            | '\u{2800}'
+            | '\u{3000}'
+            | '\u{3164}'
+            | '\u{FFA0}'
+    )
+}
+
+/// Checks that `message` renders as wide as its visible text, i.e. that it
+/// contains no blank characters with a visible advance width.
+///
+/// Returns the offending characters in order of first appearance. Such a
+/// character would add width when used as a payload character, even though it
+/// looks blank. Note that it also reports ones that were part of the carrier.
+pub fn assert_zero_width(message: &str) -> Result<(), Vec<char>> {
+    let mut offending = Vec::new();
+    for c in message.chars().filter(|&c| is_wide_blank(c)) {
+        if !offending.contains(&c) {
+            offending.push(c);
+        }
+    }
+    if offending.is_empty() {
+        Ok(())
+    } else {
+        Err(offending)
+    }
+}
+
+/// Checks that an alphabet can carry a payload: its two characters must be
+/// distinct, invisible, and not characters found in ordinary text.
+pub fn validate_alphabet(a: &Alphabet) -> Result<(), AlphabetError> {
+    if a.zero == a.one {
+        return Err(AlphabetError::Identical(a.zero));
+    }
+    for c in [a.zero, a.one] {
+        if c.is_whitespace() || c.is_alphanumeric() || c.is_ascii_graphic() {
+            return Err(AlphabetError::CommonCharacter(c));
+        }
+        if !is_invisible(c) {
+            return Err(AlphabetError::NotInvisible(c));
+        }
+    }
+    Ok(())
+}
+
+impl Alphabet {
+    /// Creates an alphabet, checking it with `validate_alphabet`.
+    pub fn new(zero: char, one: char) -> Result<Alphabet, AlphabetError> {
+        let alphabet = Alphabet { zero, one };
+        validate_alphabet(&alphabet)?;
+        Ok(alphabet)
+    }
+
+    /// The alphabet used by `create_secret` and `extract_secret`: Zero-Width
+    /// Non-Joiner (U+200C) and Zero-Width Joiner (U+200D).
+    pub const DEFAULT: Alphabet = Alphabet { zero: '\u{200C}', one: '\u{200D}' };
+    /// Zero-Width Space (U+200B) and Zero-Width No-Break Space (U+FEFF), disjoint
+    /// from `DEFAULT`.
+    pub const SECONDARY: Alphabet = Alphabet { zero: '\u{200B}', one: '\u{FEFF}' };
+    /// Tag Digit Zero (U+E0030) and Tag Digit One (U+E0031). Text copied out of
+    /// PDFs often loses zero-width characters but keeps tag characters. Each
+    /// takes 4 bytes in UTF-8 instead of 3.
+    pub const TAGS: Alphabet = Alphabet { zero: '\u{E0030}', one: '\u{E0031}' };
+
+    /// Returns `true` if `c` is one of the two characters of this alphabet.
+    fn contains(&self, c: char) -> bool {
+        c == self.zero || c == self.one
+    }
+
+    /// Iterates over the characters encoding `secret`, least significant bit first.
+    fn encode<'a>(&self, secret: &'a str) -> impl Iterator<Item = char> + 'a {
+        let Alphabet { zero, one } = *self;
+        secret
+            .bytes()
+            .flat_map(move |byte| (0..8).map(move |i| if (byte >> i) & 1 == 1 { one } else { zero }))
+    }
+
+    /// Decodes the characters of this alphabet found in `message`, ignoring
+    /// everything else. Like the free `decode`, a non-empty run of `zero` only
+    /// is rejected rather than read as NUL characters.
+    fn decode(&self, message: &str) -> Option<String> {
+        let bits: Vec<bool> = message.chars().filter(|&c| self.contains(c)).map(|c| c == self.one).collect();
+        if !bits.len().is_multiple_of(8) || (!bits.is_empty() && !bits.contains(&true)) {
+            return None;
+        }
+
+        let bytes = bits
+            .chunks_exact(8)
+            .map(|chunk| chunk.iter().enumerate().fold(0u8, |byte, (i, &bit)| byte | (u8::from(bit) << i)))
+            .collect();
+        String::from_utf8(bytes).ok()
+    }
+}
+
+/// Like `try_extract_secret`, but for a payload written with `alphabet`.
+pub fn try_extract_secret_with(message: &str, alphabet: &Alphabet) -> Result<String, ExtractError> {
+    let payload: String = message
+        .chars()
+        .filter(|&c| alphabet.contains(c))
+        .map(|c| if c == alphabet.one { I_1 } else { I_0 })
+        .collect();
+    try_extract_secret(&payload)
+}
+
+/// Returns the distinct payload characters `message` uses: those of the
+/// built-in alphabets and `NOISE_CHAR`. A channel operator can check them
+/// against the characters the channel lets through.
+pub fn payload_codepoints(message: &str) -> BTreeSet<char> {
+    let alphabets = [Alphabet::DEFAULT, Alphabet::SECONDARY, Alphabet::TAGS];
+    message
+        .chars()
+        .filter(|&c| c == NOISE_CHAR || alphabets.iter().any(|alphabet| alphabet.contains(c)))
+        .collect()
+}
+
+/// Encodes two secrets into the same span of `normal_str`, one per alphabet.
+///
+/// The two payloads are interleaved character by character and inserted at the
+/// midpoint, like `create_secret`. Each one is recovered independently with
+/// `extract_secret_with` and its alphabet. Returns `None` if the alphabets share
+/// a character, since the streams could not be told apart.
+pub fn create_secret_dual(
+    normal_str: &str,
+    first: (&Alphabet, &str),
+    second: (&Alphabet, &str),
+) -> Option<String> {
+    let (first_alphabet, first_secret) = first;
+    let (second_alphabet, second_secret) = second;
+    if first_alphabet.contains(second_alphabet.zero) || first_alphabet.contains(second_alphabet.one) {
+        return None;
+    }
+
+    let mut a = first_alphabet.encode(first_secret).fuse();
+    let mut b = second_alphabet.encode(second_secret).fuse();
+    let mid = midpoint(normal_str);
+    let mut result = String::with_capacity(normal_str.len() + (first_secret.len() + second_secret.len()) * 8 * EXP_SIZE);
+    result.push_str(&normal_str[..mid]);
+    loop {
+        match (a.next(), b.next()) {
+            (None, None) => break,
+            (x, y) => result.extend(x.into_iter().chain(y)),
+        }
+    }
+    result.push_str(&normal_str[mid..]);
+    Some(result)
+}
+
+/// Finds and decodes a secret message written with the given alphabet.
+///
+/// Characters outside `alphabet`, including those of other alphabets, are ignored.
+pub fn extract_secret_with(message: &str, alphabet: &Alphabet) -> Option<String> {
+    alphabet.decode(message)
+}
+
+/// Where the payload characters of a message sit, as reported by `position_report`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PositionReport {
+    /// A payload character comes before the first visible character.
+    pub leading: bool,
+    /// A payload character comes after the last visible character.
+    pub trailing: bool,
+    /// Share of the payload characters in the longest run, from 0.0 (no
+    /// payload) to 1.0 (one run). Spreading the payload lowers it.
+    pub clustering: f64,
+}
+
+/// Reports whether the payload of `message` is placed in suspicious positions:
+/// at the edges of the text or clustered in one spot.
+pub fn position_report(message: &str) -> PositionReport {
+    let spans = hidden_spans(message);
+    let total: usize = spans.iter().map(|span| span.char_end - span.char_start).sum();
+    let longest = spans.iter().map(|span| span.char_end - span.char_start).max().unwrap_or(0);
+    PositionReport {
+        leading: spans.first().is_some_and(|span| span.byte_start == 0),
+        trailing: spans.last().is_some_and(|span| span.byte_end == message.len()),
+        clustering: if total == 0 { 0.0 } else { longest as f64 / total as f64 },
+    }
+}
+
+/// Returns the minimum number of visible characters a carrier needs so that the
+/// invisible-to-visible ratio of the message (see `DetectabilityReport`) stays
+/// strictly below `max_ratio`.
+///
+/// `max_ratio` must be positive; otherwise no carrier is long enough and
+/// `usize::MAX` is returned.
+pub fn min_carrier_len(secret: &str, max_ratio: f64) -> usize {
+    let invisible = secret.len() * 8;
+    if invisible == 0 {
+        return 0;
+    }
+    if max_ratio.is_nan() || max_ratio <= 0.0 {
+        return usize::MAX;
+    }
+    ((invisible as f64 / max_ratio).floor() as usize).saturating_add(1)
+}
+
+/// Outcome of `extract_partial`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartialResult {
+    /// The longest prefix of the secret that could be decoded.
+    pub prefix: String,
+    /// Whether some payload characters could not be decoded into `prefix`.
+    pub truncated: bool,
+    /// Number of payload bits left over after `prefix`: an incomplete final
+    /// byte, or bytes of a character that was cut off.
+    pub lost_bits: usize,
+}
+
+/// Decodes as much of a secret as possible from a message whose payload may have
+/// been cut off.
+///
+/// Complete bytes are decoded up to the last full UTF-8 character; anything after
+/// that is reported in `lost_bits`. A cut that happens to fall exactly between
+/// two characters can't be detected and yields `truncated == false`.
+pub fn extract_partial(message: &str) -> PartialResult {
+    let filtered = remove_unnecessary_symbols(message);
+    let total_bits = filtered.len() / EXP_SIZE;
+    let bytes: Vec<u8> = filtered
+        .as_bytes()
+        .chunks_exact(8 * EXP_SIZE)
+        .map(decode_byte_table)
+        .collect();
+
+    let valid = match std::str::from_utf8(&bytes) {
+        Ok(text) => text,
+        // The prefix up to `valid_up_to` is valid UTF-8 by definition.
+        Err(error) => std::str::from_utf8(&bytes[..error.valid_up_to()]).unwrap_or_default(),
+    };
+    let lost_bits = total_bits - valid.len() * 8;
+    PartialResult {
+        prefix: valid.to_string(),
+        truncated: lost_bits > 0,
+        lost_bits,
+    }
+}
+
+/// Counts the Unicode scalar values (`char`s) in a string.
+///
+/// Zero-width characters are scalars too, so this is the count most platforms
+/// use when enforcing a character limit, even though the payload is invisible.
+pub fn scalar_count(s: &str) -> usize {
+    s.chars().count()
+}
+
+/// Counts the extended grapheme clusters in a string, i.e. the characters a
+/// reader actually perceives.
+pub fn grapheme_count(s: &str) -> usize {
+    s.graphemes(true).count()
+}
+
+
+// Unit tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[test]
+    fn assert_correct_size() {
+        assert_eq!(I_0.len(), EXP_SIZE);
+        assert_eq!(I_1.len(), EXP_SIZE);
+    }
+
+    #[test]
+    fn test_encode_decode_byte() {
+        println!("Testing encode_decode_byte");
+        let c = 'a';
+        let result = encode_byte(c as u8);
+
+        assert_eq!(result.len(), 8 * EXP_SIZE);
+        
+        let decoded_char = decode_byte(&result).expect("Decoding failed");
+        assert_eq!(decoded_char, c as u8);
+    }
+
+    #[test]
+    fn test_encode_decode_every_byte() {
+        for b in u8::MIN..=u8::MAX {
+            assert_eq!(decode_byte(&encode_byte(b)), Some(b), "byte {b:#04x}");
+        }
+    }
+
+    #[test]
+    fn test_table_decoder_matches_loop() {
+        for b in u8::MIN..=u8::MAX {
+            let encoded = encode_byte(b);
+            assert_eq!(Some(decode_byte_table(encoded.as_bytes())), decode_byte(&encoded), "byte {b:#04x}");
+        }
+    }
+
+    #[rstest]
+    #[case::high_bit_only(0x80, "00000001")]
+    #[case::all_bits(0xFF, "11111111")]
+    #[case::all_but_high_bit(0x7F, "11111110")]
+    fn test_high_bit_layout(#[case] byte: u8, #[case] bits: &str) {
+        // Bits are written least significant first, so the high bit comes last.
+        let expected: String = bits.chars().map(|bit| if bit == '1' { I_1 } else { I_0 }).collect();
+        assert_eq!(encode_byte(byte), expected);
+        assert_eq!(decode_byte(&expected), Some(byte));
+        assert_eq!(decode_bytes(&encode_bytes(&[byte, byte])), Some(vec![byte, byte]));
+    }
+
+    #[test]
+    fn test_high_bit_bytes_in_strings() {
+        // U+0080 is 0xC2 0x80, U+00FF is 0xC3 0xBF, U+07FF is 0xDF 0xBF.
+        let secret = "\u{7F}\u{80}\u{FF}\u{7FF}\u{FFFF}";
+        assert!(secret.bytes().any(|b| b == 0x80) && secret.bytes().any(|b| b == 0xBF));
+        assert_eq!(decode(&encode(secret)).as_deref(), Some(secret));
+        assert_eq!(extract_secret(&create_secret("Hello, World!", secret)).as_deref(), Some(secret));
+    }
+
+    #[test]
+    fn test_encode_decode_all_bytes_in_sequence() {
+        let data: Vec<u8> = (u8::MIN..=u8::MAX).collect();
+        let encoded = encode_bytes(&data);
+        assert_eq!(encoded.len(), 256 * 8 * EXP_SIZE);
+        assert_eq!(decode_bytes(&encoded), Some(data));
+    }
+
+    #[cfg(feature = "regex")]
+    #[rstest]
+    #[case("")]
+    #[case("Plain ASCII | with pipes")]
+    #[case("Привет 🦀 你好 \u{200B}\u{2060}\u{FEFF}")]
+    #[case(create_secret("Hello, World!", "суперsecret"))]
+    #[case(create_secret_spread("Это тест 🦀", "secret", SpreadDensity::Even))]
+    #[case(format!("\u{200D}{}\u{200C}", create_secret_with("abc", "x", &Alphabet::SECONDARY).unwrap()))]
+    fn test_scan_matches_regex(#[case] input: String) {
+        assert_eq!(remove_unnecessary_symbols_scan(&input), remove_unnecessary_symbols(&input));
+    }
+
+    #[test]
+    fn test_encode_decode_ascii() {
+        println!("Testing encode_decode_ascii");
+        let data = "Hello, World!";
+        let encoded = encode(data);
+        let decoded = decode(&encoded).expect("Decoding failed");
+        assert_eq!(data, decoded);
+    }
+
+    #[test]
+    fn test_encode_decode_other() {
+        println!("Testing encode_decode_other");
+        // Cyrillic characters to test multi-byte UTF-8 handling
+        let data = "ДАРОВА БРАТВА!";
+        let encoded = encode(data);
+        let decoded = decode(&encoded).expect("Decoding failed");
+        assert_eq!(data, decoded);
+    }
+
+    #[test]
+    fn test_full_pipeline() {
+        println!("Testing full pipeline");
+        let secret = "суперsecret";
+        let mixed_message = create_secret("Hello world", secret);
+        
+        let extracted_secret = extract_secret(&mixed_message).expect("Extraction failed");
+        assert_eq!(secret, extracted_secret);
+    }
+    
+    #[test]
+    fn test_extraction_from_complex_text() {
+        println!("Testing extraction from complex text");
+        let secret = "TopSecret123";
+        
+        let message = create_secret("Это тест, а вот и продолжение спрятанного сообщения", secret);
+
+        let extracted = extract_secret(&message).expect("Extraction failed");
+        assert_eq!(secret, extracted);
+    }
+
+    #[test]
+    fn test_counts_differ_for_emoji() {
+        // A ZWJ family emoji, a space and a crab: 18 + 1 + 4 bytes,
+        // 5 + 1 + 1 scalars, and 3 perceived characters.
+        let data = "👨\u{200D}👩\u{200D}👧 🦀";
+        assert_eq!(data.len(), 23);
+        assert_eq!(scalar_count(data), 7);
+        assert_eq!(grapheme_count(data), 3);
+    }
+
+    #[test]
+    fn test_counts_include_payload() {
+        let message = create_secret("Hello", "a");
+        assert_eq!(scalar_count(&message), 5 + 8);
+        // The payload attaches to the preceding character as a grapheme extender.
+        assert_eq!(grapheme_count(&message), 5);
+    }
+
+    #[rstest]
+    #[case::one_byte("a")]
+    #[case::two_bytes("é")]
+    #[case::three_bytes("€")]
+    #[case::four_bytes("🦀")]
+    fn test_single_char_carrier(#[case] carrier: &str) {
+        let secret = "secret";
+        let message = create_secret(carrier, secret);
+
+        // The midpoint of a single character snaps back to 0.
+        assert_eq!(message, format!("{}{}", encode(secret), carrier));
+        assert_eq!(extract_secret(&message).as_deref(), Some(secret));
+    }
+
+    /// Returns the lengths of the visible runs between payload characters.
+    fn visible_runs(message: &str) -> Vec<usize> {
+        message
+            .split(['\u{200C}', '\u{200D}'])
+            .map(|run| run.chars().count())
+            .collect()
+    }
+
+    #[test]
+    fn test_spread_fixed_gap() {
+        let carrier = "abcdefghijklmnopqrstuvwxyz0123456789";
+        let message = create_secret_spread(carrier, "a", SpreadDensity::FixedGap(3));
+
+        assert_eq!(visible_runs(&message), [3, 3, 3, 3, 3, 3, 3, 3, 12]);
+        assert_eq!(extract_secret(&message).as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn test_spread_fixed_gap_overflows_carrier() {
+        let message = create_secret_spread("abcdef", "a", SpreadDensity::FixedGap(3));
+
+        // Two payload characters fit inside the carrier, the rest trail it.
+        assert_eq!(visible_runs(&message), [3, 3, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(extract_secret(&message).as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn test_spread_fixed_gap_zero_is_front() {
+        let message = create_secret_spread("abc", "a", SpreadDensity::FixedGap(0));
+        assert_eq!(message, format!("{}abc", encode("a")));
+    }
+
+    #[test]
+    fn test_spread_even() {
+        let carrier = "x".repeat(90);
+        let message = create_secret_spread(&carrier, "a", SpreadDensity::Even);
+
+        assert_eq!(visible_runs(&message), [10; 9]);
+        assert_eq!(extract_secret(&message).as_deref(), Some("a"));
+    }
+
+    #[rstest]
+    fn test_spread_round_trip(
+        #[values("", "x", "Hello, World!", "Это тест 🦀 with graphemes")] carrier: &str,
+        #[values("", "secret123", "суперsecret", "Rustacean 🦀")] secret: &str,
+        #[values(SpreadDensity::Even, SpreadDensity::FixedGap(0), SpreadDensity::FixedGap(1), SpreadDensity::FixedGap(3))]
+        density: SpreadDensity,
+    ) {
+        let message = create_secret_spread(carrier, secret, density);
+        assert_eq!(extract_secret(&message).as_deref(), Some(secret));
+
+        let visible: String = message.chars().filter(|c| !matches!(c, '\u{200C}' | '\u{200D}')).collect();
+        assert_eq!(visible, carrier);
+    }
+
+    #[test]
+    fn test_detectability_short_contiguous_is_high() {
+        let message = create_secret("Hi", "a fairly long secret message");
+        let report = detectability(&message);
+
+        assert!(report.contiguous);
+        assert!(report.uncommon_code_points.is_empty());
+        assert_eq!(report.score, 85);
+    }
+
+    #[test]
+    fn test_detectability_long_spread_is_low() {
+        let carrier = "A long and perfectly ordinary sentence about the weather. ".repeat(20);
+        let message = create_secret_spread(&carrier, "hi", SpreadDensity::Even);
+        let report = detectability(&message);
+
+        assert!(!report.contiguous);
+        assert!(report.score < 10, "score was {}", report.score);
+    }
+
+    #[test]
+    fn test_detectability_uncommon_code_points() {
+        let report = detectability("plain\u{200B} text\u{FEFF}\u{200B}");
+        assert_eq!(report.uncommon_code_points, ['\u{200B}', '\u{FEFF}']);
+        assert!(!report.contiguous);
+    }
+
+    #[test]
+    fn test_detectability_plain_text() {
+        let report = detectability("Nothing to see here");
+        assert_eq!(report.score, 0);
+        assert_eq!(report.invisible_ratio, 0.0);
+    }
+
+    #[test]
+    fn test_extract_from_html_paragraph() {
+        let html = format!("<p class=\"note\">{}</p>", create_secret("Hello & welcome", "secret"));
+        assert_eq!(extract_secret_from_html(&html).as_deref(), Some("secret"));
+    }
+
+    #[test]
+    fn test_extract_from_html_entities() {
+        let payload = encode("secret")
+            .chars()
+            .enumerate()
+            .map(|(i, c)| match (c, i % 2) {
+                ('\u{200C}', 0) => "&#8204;",
+                ('\u{200C}', _) => "&zwnj;",
+                (_, 0) => "&#x200D;",
+                _ => "&zwj;",
+            })
+            .collect::<String>();
+        let html = format!("<div>Fish &amp; <b>chips</b>{}</div>", payload);
+
+        assert_eq!(extract_secret_from_html(&html).as_deref(), Some("secret"));
+    }
+
+    #[test]
+    fn test_extract_from_html_ignores_markup() {
+        let html = format!(
+            "<p title=\"{}\">{}</p><!-- {} -->",
+            encode("attribute"),
+            create_secret("Visible", "body"),
+            encode("comment")
+        );
+        assert_eq!(extract_secret_from_html(&html).as_deref(), Some("body"));
+    }
+
+    #[test]
+    fn test_html_comment_round_trip() {
+        let html = create_secret_html_comment("<p>Hello &amp; welcome</p>", "секрет");
+        assert!(html.starts_with("<p>Hello &amp; welcome</p><!--"));
+        assert_eq!(extract_secret_from_html(&html).as_deref(), Some("секрет"));
+
+        // The rendered page, and text copied from it, loses the comment and
+        // with it the secret.
+        assert_eq!(strip_tags(&html), "Hello &amp; welcome");
+        assert_eq!(extract_secret_from_html(&strip_tags(&html)).as_deref(), Some(""));
+        assert_eq!(extract_secret_from_html("<p>Hi</p><!-- not a payload -->").as_deref(), Some(""));
+    }
+
+    #[test]
+    fn test_extract_from_url() {
+        let message = create_secret("50% off, today only", "секрет");
+        let encoded: String = message
+            .bytes()
+            .map(|b| if b.is_ascii_alphanumeric() { (b as char).to_string() } else { format!("%{b:02X}") })
+            .collect();
+        assert!(encoded.contains("%E2%80%8C"));
+        assert_eq!(extract_secret_from_url(&encoded).as_deref(), Some("секрет"));
+        assert_eq!(extract_secret_from_url(&encoded.to_lowercase()).as_deref(), Some("секрет"));
+
+        // Not percent-encoded, so the literal `%` is left alone.
+        assert_eq!(extract_secret_from_url(&message).as_deref(), Some("секрет"));
+        let plain = create_secret("Code %41 means 100%", "hi");
+        assert_eq!(extract_secret_from_url(&plain).as_deref(), Some("hi"));
+        assert_eq!(percent_decode("100%ZZ %e2%82%ac %"), "100%ZZ € %");
+    }
+
+    #[test]
+    fn test_try_extract_secret() {
+        let message = create_secret("Hello, World!", "секрет");
+        assert_eq!(try_extract_secret(&message).as_deref(), Ok("секрет"));
+        assert_eq!(try_extract_secret("Hello"), Err(ExtractError::NoPayload));
+        assert_eq!(try_extract_secret(&create_secret("Hello", "")), Err(ExtractError::NoPayload));
+        assert_eq!(
+            try_extract_secret(&message.replacen(I_1, "", 3)),
+            Err(ExtractError::IncompleteByte { bits: 5 })
+        );
+        assert_eq!(try_extract_secret(&format!("a{}b", I_0.repeat(16))), Err(ExtractError::OnlyNulBytes));
+        assert_eq!(try_extract_secret(&encode_bytes(&[0xFF])), Err(ExtractError::InvalidUtf8));
+
+        let cut = &message[..hidden_spans(&message)[0].byte_end - 4 * EXP_SIZE];
+        for message in [message.as_str(), "Hello", cut] {
+            let expected = extract_secret(message).filter(|secret| !secret.is_empty());
+            assert_eq!(try_extract_secret(message).ok(), expected);
+        }
+    }
+
+    #[test]
+    fn test_extract_raw() {
+        let message = create_secret("Hello, World!", "hé");
+        assert_eq!(extract_raw(&message), Some(vec![0x68, 0xC3, 0xA9]));
+        assert_eq!(extract_raw("Hello"), Some(vec![]));
+        assert_eq!(extract_raw(&format!("a{}b", encode_bytes(&[0xFF, 0x00]))), Some(vec![0xFF, 0x00]));
+        assert_eq!(extract_raw(&message.replacen(I_1, "", 1)), None);
+
+        let framed = create_secret_with_footer("Hello", "hi");
+        assert_eq!(extract_raw(&framed), Some(vec![0xFD, 2, b'h', b'i', 0xFC]));
+    }
+
+    #[test]
+    fn test_payload_codepoints() {
+        let message = create_secret("Hello, World!", "секрет");
+        assert_eq!(payload_codepoints(&message), BTreeSet::from(['\u{200C}', '\u{200D}']));
+
+        let noisy = create_secret_noisy("Hello, World!", "секрет", NoiseLevel::High, 1);
+        assert_eq!(payload_codepoints(&noisy), BTreeSet::from(['\u{200C}', '\u{200D}', NOISE_CHAR]));
+
+        let tagged = create_secret_with("Hello", "hi", &Alphabet::TAGS).unwrap();
+        assert_eq!(payload_codepoints(&tagged), BTreeSet::from(['\u{E0030}', '\u{E0031}']));
+        // Only code points that actually occur are reported.
+        assert_eq!(payload_codepoints("a\u{200D}\u{200D}b"), BTreeSet::from(['\u{200D}']));
+        assert_eq!(payload_codepoints("Hello\u{200B}"), BTreeSet::from(['\u{200B}']));
+        assert!(payload_codepoints("Hello").is_empty());
+    }
+
+    #[test]
+    fn test_tags_survive_pdf_copy() {
+        // Copying from some PDF viewers drops zero-width characters but keeps tags.
+        let pdf_copy = |message: &str| -> String {
+            message.chars().filter(|c| !matches!(c, '\u{200B}'..='\u{200D}' | '\u{2060}' | '\u{FEFF}')).collect()
+        };
+        assert_eq!(validate_alphabet(&Alphabet::TAGS), Ok(()));
+
+        let tagged = create_secret_with("Hello, World!", "секрет", &Alphabet::TAGS).unwrap();
+        let copied = pdf_copy(&tagged);
+        assert_eq!(try_extract_secret_with(&copied, &Alphabet::TAGS).as_deref(), Ok("секрет"));
+        assert_eq!(try_extract_secret(&copied), Err(ExtractError::NoPayload));
+
+        let zero_width = create_secret("Hello, World!", "секрет");
+        assert_eq!(pdf_copy(&zero_width), "Hello, World!");
+        assert_eq!(try_extract_secret(&pdf_copy(&zero_width)), Err(ExtractError::NoPayload));
+        assert_eq!(try_extract_secret_with(&zero_width, &Alphabet::DEFAULT).as_deref(), Ok("секрет"));
+        assert_eq!(
+            try_extract_secret_with(&tagged.replacen('\u{E0031}', "", 1), &Alphabet::TAGS),
+            Err(ExtractError::IncompleteByte { bits: 7 })
+        );
+    }
+
+    #[test]
+    fn test_same_secret() {
+        let a = create_secret("Hello, World!", "секрет");
+        let b = create_secret_spread("Something else entirely", "секрет", SpreadDensity::Even);
+        assert_eq!(same_secret(&a, &b), Some(true));
+        assert_eq!(same_secret(&a, &create_secret("Hello, World!", "other")), Some(false));
+        assert_eq!(same_secret(&a, "Hello, World!"), None);
+        assert_eq!(same_secret(&a.replacen(I_0, "", 1), &a), None);
+    }
+
+    #[test]
+    fn test_try_extract_secret_from_html() {
+        let corrupted = create_secret("Hello", "secret").replacen(I_0, "", 1);
+        assert_eq!(
+            try_extract_secret_from_html(&format!("<p>{corrupted}</p>")),
+            Err(ExtractError::IncompleteByte { bits: 7 })
+        );
+        let html = format!("<p>{corrupted}</p>{}", create_secret_html_comment("", "backup"));
+        assert_eq!(try_extract_secret_from_html(&html).as_deref(), Ok("backup"));
+        assert_eq!(try_extract_secret_from_html("<p>Hi</p>"), Err(ExtractError::NoPayload));
+    }
+
+    #[test]
+    fn test_decode_entities_keeps_unknown() {
+        assert_eq!(decode_entities("a &bogus; b & c &#xZZ; &lt;"), "a &bogus; b & c &#xZZ; <");
+    }
+
+    #[test]
+    fn test_default_alphabet_matches_constants() {
+        assert_eq!(Alphabet::DEFAULT.zero.to_string(), I_0);
+        assert_eq!(Alphabet::DEFAULT.one.to_string(), I_1);
+
+        let message = create_secret("Hello, World!", "суперsecret");
+        assert_eq!(extract_secret_with(&message, &Alphabet::DEFAULT).as_deref(), Some("суперsecret"));
+    }
+
+    #[test]
+    fn test_extract_secret_with_rejects_nul_only() {
+        let zeros = create_secret_from_bits("ab", &[false; 16]);
+        assert_eq!(extract_secret(&zeros), None);
+        let zeros = format!("a{}b", Alphabet::SECONDARY.zero.to_string().repeat(16));
+        assert_eq!(extract_secret_with(&zeros, &Alphabet::SECONDARY), None);
+        assert_eq!(try_extract_secret_with(&zeros, &Alphabet::SECONDARY), Err(ExtractError::OnlyNulBytes));
+        assert_eq!(extract_secret_with("ab", &Alphabet::SECONDARY).as_deref(), Some(""));
+    }
+
+    #[test]
+    fn test_validate_alphabet() {
+        assert_eq!(validate_alphabet(&Alphabet::DEFAULT), Ok(()));
+        assert_eq!(validate_alphabet(&Alphabet::SECONDARY), Ok(()));
+        assert_eq!(Alphabet::new('\u{2060}', '\u{E0041}'), Ok(Alphabet { zero: '\u{2060}', one: '\u{E0041}' }));
+    }
+
+    #[rstest]
+    #[case('\u{200C}', '\u{200C}', AlphabetError::Identical('\u{200C}'))]
+    #[case('a', '\u{200D}', AlphabetError::CommonCharacter('a'))]
+    #[case('\u{200C}', ' ', AlphabetError::CommonCharacter(' '))]
+    #[case('\u{200C}', '\u{00A0}', AlphabetError::CommonCharacter('\u{00A0}'))]
+    #[case('\u{200C}', 'ж', AlphabetError::CommonCharacter('ж'))]
+    #[case('\u{0301}', '\u{200D}', AlphabetError::NotInvisible('\u{0301}'))]
+    #[case('\u{200C}', '\u{2028}', AlphabetError::CommonCharacter('\u{2028}'))]
+    #[case('\u{200C}', '\u{00AD}', AlphabetError::NotInvisible('\u{00AD}'))]
+    fn test_validate_alphabet_errors(#[case] zero: char, #[case] one: char, #[case] expected: AlphabetError) {
+        assert_eq!(validate_alphabet(&Alphabet { zero, one }), Err(expected));
+        assert_eq!(Alphabet::new(zero, one), Err(expected));
+    }
+
+    #[rstest]
+    #[case("Hello, World!", "first secret", "другой")]
+    #[case("x", "a", "a much longer second stream 🦀")]
+    #[case("", "", "only the second")]
+    fn test_dual_streams(#[case] carrier: &str, #[case] first: &str, #[case] second: &str) {
+        let message = create_secret_dual(carrier, (&Alphabet::DEFAULT, first), (&Alphabet::SECONDARY, second))
+            .expect("Alphabets are disjoint");
+
+        assert_eq!(extract_secret_with(&message, &Alphabet::DEFAULT).as_deref(), Some(first));
+        assert_eq!(extract_secret_with(&message, &Alphabet::SECONDARY).as_deref(), Some(second));
+        // The default extraction only sees the first stream.
+        assert_eq!(extract_secret(&message).as_deref(), Some(first));
+    }
+
+    #[test]
+    fn test_dual_streams_interleave() {
+        let message = create_secret_dual("ab", (&Alphabet::DEFAULT, "\u{0}"), (&Alphabet::SECONDARY, "\u{7F}"))
+            .expect("Alphabets are disjoint");
+        assert_eq!(message, format!("a{}\u{200C}\u{200B}b", "\u{200C}\u{FEFF}".repeat(7)));
+    }
+
+    #[test]
+    fn test_dual_streams_reject_overlap() {
+        let overlapping = Alphabet { zero: '\u{200B}', one: '\u{200D}' };
+        assert_eq!(create_secret_dual("ab", (&Alphabet::DEFAULT, "a"), (&overlapping, "b")), None);
+    }
+
+    #[rstest]
+    #[case("ab")]
+    #[case("  Hello, World!  ")]
+    #[case("\nПривет\t")]
+    fn test_survives_trimmed_edges(#[case] carrier: &str) {
+        let message = create_secret(carrier, "secret");
+        assert!(!payload_at_edge(&message));
+
+        // Simulate an input that trims whitespace and invisibles at both ends.
+        let trimmed = message.trim_matches(|c: char| c.is_whitespace() || Alphabet::DEFAULT.contains(c));
+        assert_eq!(extract_secret(trimmed).as_deref(), Some("secret"));
+    }
+
+    #[test]
+    fn test_payload_at_edge() {
+        assert!(payload_at_edge(&create_secret("a", "secret")));
+        assert!(payload_at_edge(&create_secret("", "secret")));
+        assert!(!payload_at_edge(&create_secret("a", "")));
+        assert!(payload_at_edge(&format!("ab{}", encode("x"))));
+    }
+
+    #[test]
+    fn test_hidden_spans_single_run() {
+        let message = create_secret("Привет", "a");
+        // "При" is 3 chars and 6 bytes; the payload is 8 chars of 3 bytes each.
+        assert_eq!(hidden_spans(&message), [Span { byte_start: 6, byte_end: 30, char_start: 3, char_end: 11 }]);
+        assert!(hidden_spans("No payload").is_empty());
+    }
+
+    #[test]
+    fn test_hidden_spans_two_runs() {
+        let payload = encode("ab");
+        let (first, second) = payload.split_at(payload.len() / 2);
+        let message = format!("ab{first}cde{second}ü");
+        let spans = hidden_spans(&message);
+
+        assert_eq!(
+            spans,
+            [
+                Span { byte_start: 2, byte_end: 26, char_start: 2, char_end: 10 },
+                Span { byte_start: 29, byte_end: 53, char_start: 13, char_end: 21 },
+            ]
+        );
+        for span in &spans {
+            assert!(message[span.byte_start..span.byte_end].chars().all(|c| Alphabet::DEFAULT.contains(c)));
+        }
+        assert_eq!(extract_secret(&message).as_deref(), Some("ab"));
+    }
+
+    #[test]
+    fn test_hidden_spans_spread() {
+        let message = create_secret_spread("abcdef", "a", SpreadDensity::FixedGap(2));
+        let starts: Vec<usize> = hidden_spans(&message).iter().map(|span| span.char_start).collect();
+        assert_eq!(starts, [2, 5, 8]);
+    }
+
+    #[test]
+    fn test_extract_all_is_ordered() {
+        let secrets = ["zebra", "apple", "мango"];
+        let message = format!(
+            "First {} then {}, and finally {} at the end.",
+            encode(secrets[0]),
+            encode(secrets[1]),
+            encode(secrets[2])
+        );
+        assert_eq!(extract_all(&message), secrets);
+
+        let reversed = format!("{}a{}b{}", encode(secrets[2]), encode(secrets[1]), encode(secrets[0]));
+        assert_eq!(extract_all(&reversed), ["мango", "apple", "zebra"]);
+    }
+
+    #[test]
+    fn test_extract_all_skips_broken_runs() {
+        let message = format!("a{}b{}c", &encode("x")[EXP_SIZE..], encode("ok"));
+        assert_eq!(extract_all(&message), ["ok"]);
+        assert!(extract_all("Nothing here").is_empty());
+    }
+
+    #[test]
+    fn test_redact_payload() {
+        let message = create_secret("Hello, World!", "secret");
+        assert_eq!(redact_payload(&message, "⟦hidden⟧"), "Hello,⟦hidden⟧ World!");
+
+        // Each run gets its own placeholder.
+        let spread = create_secret_spread("abc", "a", SpreadDensity::FixedGap(1));
+        assert_eq!(redact_payload(&spread, "*"), "a*b*c*");
+
+        assert_eq!(redact_payload("No payload", "*"), "No payload");
+    }
+
+    #[test]
+    fn test_recover_carrier() {
+        let message = create_secret("Hello, World!", "secret");
+        assert_eq!(recover_carrier(&message), "Hello, World!");
+    }
+
+    #[test]
+    fn test_same_carrier_normalization() {
+        assert!(!same_carrier("Café", "Cafe\u{301}", false));
+        assert!(same_carrier("Café", "Cafe\u{301}", true));
+        assert!(!same_carrier("Café", "Cafe", true));
+    }
+
+    #[rstest]
+    #[case("Cafe\u{301} au lait")]
+    #[case("e\u{301}")]
+    #[case("Zoe\u{308} and Bjo\u{308}rk")]
+    fn test_create_secret_checked_decomposed(#[case] carrier: &str) {
+        let message = create_secret_checked(carrier, "secret", true).expect("Check failed");
+        assert_eq!(message, create_secret(carrier, "secret"));
+        assert!(create_secret_checked(carrier, "secret", false).is_ok());
+    }
+
+    #[test]
+    fn test_carrier_with_pipe() {
+        let message = create_secret("a|b|c", "secret");
+        assert_eq!(extract_secret(&message).as_deref(), Some("secret"));
+        assert!(create_secret_checked("a|b|c", "secret", false).is_ok());
+    }
+
+    #[test]
+    fn test_create_secret_with() {
+        let message = create_secret_with("Hello, World!", "secret", &Alphabet::SECONDARY).expect("Valid inputs");
+        assert_eq!(extract_secret_with(&message, &Alphabet::SECONDARY).as_deref(), Some("secret"));
+        assert_eq!(
+            create_secret_with("Hello, World!", "secret", &Alphabet::DEFAULT),
+            Ok(create_secret("Hello, World!", "secret"))
+        );
+    }
+
+    #[test]
+    fn test_encode_error_carrier_contains_alphabet() {
+        let carrier = create_secret("Hello", "old");
+        assert_eq!(create_secret_checked(&carrier, "new", false), Err(EncodeError::CarrierContainsAlphabet));
+        assert_eq!(
+            create_secret_with(&carrier, "new", &Alphabet::DEFAULT),
+            Err(EncodeError::CarrierContainsAlphabet)
+        );
+        // Characters of another alphabet are fine.
+        assert!(create_secret_with(&carrier, "new", &Alphabet::SECONDARY).is_ok());
+    }
+
+    #[test]
+    fn test_encode_error_payload_too_large() {
+        let secret = "x".repeat(MAX_SECRET_LEN + 1);
+        let expected = Err(EncodeError::PayloadTooLarge { len: MAX_SECRET_LEN + 1, max: MAX_SECRET_LEN });
+        assert_eq!(create_secret_checked("Hello", &secret, false), expected);
+        assert_eq!(create_secret_with("Hello", &secret, &Alphabet::DEFAULT), expected);
+    }
+
+    #[test]
+    fn test_encode_error_round_trip_failed() {
+        let message = create_secret("Hello", "secret");
+        assert_eq!(verify_round_trip(&message, "Hello", "secret", false), Ok(()));
+        assert_eq!(verify_round_trip(&message, "Hello!", "secret", false), Err(EncodeError::RoundTripFailed));
+        let damaged = message.replacen(I_0, "", 1);
+        assert_eq!(verify_round_trip(&damaged, "Hello", "secret", false), Err(EncodeError::RoundTripFailed));
+    }
+
+    #[test]
+    fn test_encode_error_alphabet_invalid() {
+        let alphabet = Alphabet { zero: 'a', one: 'b' };
+        assert_eq!(
+            create_secret_with("Hello", "secret", &alphabet),
+            Err(EncodeError::AlphabetInvalid(AlphabetError::CommonCharacter('a')))
+        );
+    }
+
+    #[rstest]
+    fn test_min_carrier_len(
+        #[values("a", "secret", "суперsecret 🦀")] secret: &str,
+        #[values(0.1, 0.25, 0.5, 1.0, 3.0)] max_ratio: f64,
+    ) {
+        let min = min_carrier_len(secret, max_ratio);
+
+        let long_enough = create_secret(&"x".repeat(min), secret);
+        assert!(detectability(&long_enough).invisible_ratio < max_ratio);
+
+        let too_short = create_secret(&"x".repeat(min - 1), secret);
+        assert!(detectability(&too_short).invisible_ratio >= max_ratio);
+    }
+
+    #[test]
+    fn test_min_carrier_len_edge_cases() {
+        assert_eq!(min_carrier_len("", 0.5), 0);
+        assert_eq!(min_carrier_len("a", 0.0), usize::MAX);
+        assert_eq!(min_carrier_len("a", f64::NAN), usize::MAX);
+        assert_eq!(min_carrier_len("a", 8.0), 2);
+    }
+
+    #[rstest]
+    #[case::hebrew(create_secret("שלום עולם", "secret"))]
+    #[case::arabic_embedding(format!("\u{202B}{}\u{202C}", create_secret("مرحبا بالعالم", "secret")))]
+    #[case::isolated_payload(format!("abc\u{2067}{}\u{2069}def", encode("secret")))]
+    #[case::marks_inside_payload({
+        let payload = encode("secret");
+        let (head, tail) = payload.split_at(payload.len() / 2);
+        format!("\u{200F}שלום{head}\u{200E}\u{202E}{tail}\u{202C}עולם")
+    })]
+    fn test_extract_around_bidi(#[case] message: String) {
+        assert_eq!(extract_secret(&message).as_deref(), Some("secret"));
+    }
+
+    #[test]
+    fn test_extract_after_space_normalization() {
+        let payload = encode("secret");
+        let (head, tail) = payload.split_at(payload.len() / 2);
+        let message = format!("  Hello,   {head}  \t {tail}\n\n  World!  ");
+        // What a chat app might do: collapse whitespace runs and trim the ends.
+        let normalized = message.split_ascii_whitespace().collect::<Vec<_>>().join(" ");
+        assert_ne!(normalized, message);
+        assert_eq!(extract_secret(&normalized).as_deref(), Some("secret"));
+        assert_eq!(remove_unnecessary_symbols(&normalized), payload);
+    }
+
+    #[test]
+    fn test_split_message() {
+        let message = create_secret("Hello, World!", "секрет");
+        let (visible, payload) = split_message(&message);
+        assert_eq!(visible, "Hello, World!");
+        assert_eq!(payload, encode("секрет"));
+        assert_eq!(extract_secret(&format!("{visible}{payload}")).as_deref(), Some("секрет"));
+        assert_eq!(split_message("plain"), ("plain".to_string(), String::new()));
+    }
+
+    #[rstest]
+    fn test_redundant_survives_one_damaged_copy(
+        #[values(0, 1, 2)] damaged: usize,
+        #[values("flip", "drop", "insert")] damage: &str,
+    ) {
+        let message = create_secret_redundant("Hello, World!", "секрет", 3).expect("Carrier too short");
+        let spans = hidden_spans(&message);
+        assert_eq!(spans.len(), 3);
+
+        // Damage a payload character in the middle of one copy.
+        let span = spans[damaged];
+        let at = span.byte_start + (span.char_end - span.char_start) / 2 * EXP_SIZE;
+        let replacement = match (damage, &message[at..at + EXP_SIZE]) {
+            ("flip", I_0) => I_1.to_string(),
+            ("flip", _) => I_0.to_string(),
+            ("drop", _) => String::new(),
+            _ => format!("{I_0}{}", &message[at..at + EXP_SIZE]),
+        };
+        let damaged_message = format!("{}{}{}", &message[..at], replacement, &message[at + EXP_SIZE..]);
+
+        assert_eq!(extract_secret_redundant(&damaged_message).as_deref(), Some("секрет"));
+    }
+
+    #[test]
+    fn test_redundant_all_copies_damaged() {
+        let message = create_secret_redundant("Hello, World!", "secret", 2).expect("Carrier too short");
+        let mut damaged = message.clone();
+        for span in hidden_spans(&message).into_iter().rev() {
+            damaged.replace_range(span.byte_start..span.byte_start + EXP_SIZE, "");
+        }
+        assert_eq!(extract_secret_redundant(&message).as_deref(), Some("secret"));
+        assert_eq!(extract_secret_redundant(&damaged), None);
+        assert_eq!(extract_secret_redundant("Hello, World!"), None);
+    }
+
+    #[test]
+    fn test_redundant_needs_room_for_copies() {
+        assert_eq!(create_secret_redundant("abc", "secret", 0), None);
+        assert_eq!(create_secret_redundant("abc", "secret", 3), None);
+        let message = create_secret_redundant("abc", "secret", 2).expect("Carrier too short");
+        assert_eq!(recover_carrier(&message), "abc");
+        assert_eq!(hidden_spans(&message).len(), 2);
+    }
+
+    #[test]
+    fn test_transform_hooks() {
+        let xor = |bytes: &[u8]| bytes.iter().map(|b| b ^ 0x5A).collect::<Vec<u8>>();
+        let message = create_secret_transformed("Hello, World!", "секрет", xor);
+
+        assert_eq!(extract_secret_transformed(&message, xor).as_deref(), Some("секрет"));
+        assert_ne!(remove_unnecessary_symbols(&message), encode("секрет"));
+        assert_ne!(extract_secret(&message).as_deref(), Some("секрет"));
+        let identity = |bytes: &[u8]| bytes.to_vec();
+        assert_eq!(create_secret_transformed("Hello, World!", "секрет", identity), create_secret("Hello, World!", "секрет"));
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        let visible = "The quick brown fox jumps over the lazy dog";
+        let middle = create_secret(visible, "секрет");
+        let spread = create_secret_spread(visible, "секрет", SpreadDensity::Even);
+        let front = format!("{}{visible}", encode("секрет"));
+        assert_ne!(middle, spread);
+
+        assert_eq!(canonicalize(&spread), middle);
+        assert_eq!(canonicalize(&front), middle);
+        assert_eq!(canonicalize(&middle), middle);
+        let broken = middle.replacen(I_0, "", 1);
+        assert_eq!(canonicalize(&broken), broken);
+    }
+
+    #[test]
+    fn test_create_secret_templated() {
+        let message = create_secret_templated("Best regards,<HERE> Alice", "<HERE>", "секрет").expect("Placeholder missing");
+        assert_eq!(message, format!("Best regards,{} Alice", encode("секрет")));
+        assert_eq!(extract_secret(&message).as_deref(), Some("секрет"));
+        assert_eq!(recover_carrier(&message), "Best regards, Alice");
+
+        let twice = create_secret_templated("Ёж<HERE>ик<HERE>", "<HERE>", "a").expect("Placeholder missing");
+        assert_eq!(twice, format!("Ёж{}ик<HERE>", encode("a")));
+        assert_eq!(create_secret_templated("Best regards, Alice", "<HERE>", "a"), None);
+        assert_eq!(create_secret_templated("Best regards, Alice", "", "a"), None);
+    }
+
+    #[rstest]
+    fn test_rejects_single_symbol_runs(#[values(8, 24, 800)] len: usize, #[values(I_0, I_1)] symbol: &str) {
+        let message = format!("Hello,{} World!", symbol.repeat(len));
+        assert_eq!(extract_secret(&message), None);
+        assert!(extract_all(&message).is_empty());
+    }
+
+    #[rstest]
+    #[case::empty("", 0, 0)]
+    #[case::empty_past_end("", 5, 0)]
+    #[case::ascii("abc", 2, 2)]
+    #[case::zero("д🦀", 0, 0)]
+    #[case::inside_two_byte("д🦀", 1, 0)]
+    #[case::inside_four_byte_start("д🦀", 3, 2)]
+    #[case::inside_four_byte_end("д🦀", 5, 2)]
+    #[case::at_end("д🦀", 6, 6)]
+    #[case::past_end("д🦀", 100, 6)]
+    #[case::usize_max("д🦀", usize::MAX, 6)]
+    #[case::combining("e\u{301}", 2, 1)]
+    fn test_snap_to_char_boundary(#[case] s: &str, #[case] idx: usize, #[case] expected: usize) {
+        assert_eq!(snap_to_char_boundary(s, idx), expected);
+        assert!(s.is_char_boundary(expected));
+    }
+
+    #[test]
+    fn test_verification_token() {
+        let (message, token) = create_secret_with_token("Hello, World!", "secret");
+        assert_eq!(message, create_secret("Hello, World!", "secret"));
+        assert_eq!(token.len(), 8);
+        assert!(verify_token(&message, &token));
+        assert!(verify_token(&message, &token.to_uppercase()));
+        assert!(verify_token(&message.replace("World", "there"), &token));
+
+        assert!(!verify_token(&recover_carrier(&message), &token));
+        assert!(!verify_token(&message.replacen(I_0, I_1, 1), &token));
+        let (_, other) = create_secret_with_token("Hello, World!", "secreT");
+        assert_ne!(token, other);
+    }
+
+    #[rstest]
+    #[case::crab("🦀")]
+    #[case::heart_with_selector("❤\u{FE0F}")]
+    #[case::skin_tone("👍🏽")]
+    #[case::flag("🇩🇪")]
+    #[case::keycap("1\u{FE0F}\u{20E3}")]
+    fn test_create_secret_emoji(#[case] emoji: &str) {
+        let message = create_secret_emoji(emoji, "секрет").expect("Not an emoji");
+        assert_eq!(message, format!("{emoji}{}", encode("секрет")));
+        assert_eq!(recover_carrier(&message), emoji);
+        assert_eq!(grapheme_count(&message), 1);
+        assert_eq!(extract_secret(&message).as_deref(), Some("секрет"));
+    }
+
+    #[rstest]
+    #[case::empty("")]
+    #[case::text("a")]
+    #[case::digit("1")]
+    #[case::two_emoji("🦀🦀")]
+    #[case::emoji_and_text("🦀 hi")]
+    #[case::zwj_sequence("👨\u{200D}👩\u{200D}👧")]
+    fn test_create_secret_emoji_rejects(#[case] emoji: &str) {
+        assert_eq!(create_secret_emoji(emoji, "секрет"), None);
+    }
+
+    #[test]
+    fn test_reflow_after_edit() {
+        let message = create_secret("Hello, World!", "секрет");
+        // The user deletes a word and types inside the payload by hand.
+        let mut edited = message.replacen("Hello", "", 1);
+        edited.insert_str(1 + 20 * EXP_SIZE, " big");
+        assert_eq!(hidden_spans(&edited).len(), 2);
+
+        let repaired = reflow(&edited).expect("Payload lost");
+        assert_eq!(repaired, create_secret(", big World!", "секрет"));
+        assert_eq!(hidden_spans(&repaired).len(), 1);
+        assert_eq!(extract_secret(&repaired).as_deref(), Some("секрет"));
+
+        assert_eq!(reflow(&edited.replacen(I_1, "", 1)), None);
+    }
+
+
+    #[test]
+    fn test_rewrap() {
+        let message = create_secret("Hello, World!", "секрет");
+        let rewrapped = rewrap(&message, "A different cover story").expect("No secret");
+        assert_eq!(extract_secret(&rewrapped).as_deref(), Some("секрет"));
+        assert_eq!(recover_carrier(&rewrapped), "A different cover story");
+
+        assert_eq!(rewrap("Hello, World!", "New"), None);
+        assert_eq!(rewrap(&message.replacen(I_1, "", 1), "New"), None);
+    }
+
+    #[test]
+    fn test_whitespace_only_carrier() {
+        for carrier in [" ", "  \n\t ", "\u{3000}\n"] {
+            assert_eq!(carrier_warning(carrier), Some(CarrierWarning::WhitespaceOnlyCarrier));
+            let message = create_secret_checked(carrier, "секрет", false).expect("Encoding failed");
+            assert_eq!(extract_secret(&message).as_deref(), Some("секрет"));
+            assert_eq!(recover_carrier(&message), carrier);
+        }
+        assert_eq!(carrier_warning(""), None);
+        assert_eq!(carrier_warning(" Hello "), None);
+    }
+
+    #[test]
+    fn test_empty_carrier() {
+        assert_eq!(create_secret_checked("", "secret", false), Err(EncodeError::EmptyCarrier));
+        assert_eq!(create_secret_with("", "secret", &Alphabet::DEFAULT), Err(EncodeError::EmptyCarrier));
+        assert_eq!(create_secret_guarded("", "secret", EmptyCarrier::default()), Err(EncodeError::EmptyCarrier));
+
+        let message = create_secret_guarded("", "secret", EmptyCarrier::UseDefault).expect("Encoding failed");
+        assert_eq!(recover_carrier(&message), DEFAULT_CARRIER);
+        assert_eq!(extract_secret(&message).as_deref(), Some("secret"));
+        assert_eq!(
+            create_secret_guarded("Hi there", "secret", EmptyCarrier::UseDefault),
+            create_secret_checked("Hi there", "secret", false)
+        );
+    }
+
+    #[test]
+    fn test_create_secret_seeded() {
+        let visible = "The quick brown fox jumps over the lazy dog";
+        let message = create_secret_seeded(visible, "секрет", 42);
+
+        assert_eq!(message, create_secret_seeded(visible, "секрет", 42));
+        assert_eq!(extract_secret(&message).as_deref(), Some("секрет"));
+        assert_eq!(recover_carrier(&message), visible);
+        assert!(!payload_at_edge(&message));
+        assert!(verify_placement(&message, 42));
+
+        let other = create_secret_seeded(visible, "секрет", 43);
+        assert_ne!(hidden_spans(&message), hidden_spans(&other));
+        assert!(!verify_placement(&other, 42));
+        assert!(!verify_placement(&create_secret(visible, "секрет"), 42));
+        assert!(!verify_placement(visible, 42));
+    }
+
+    #[rstest]
+    #[case::one_run_per_byte("The quick brown fox", "secret", 6, true)]
+    #[case::more_bytes_than_positions("abc", "secret", 2, true)]
+    #[case::single_char("a", "hi", 1, true)]
+    #[case::empty_secret("abc", "", 0, false)]
+    fn test_create_secret_seeded_runs(
+        #[case] visible: &str,
+        #[case] secret: &str,
+        #[case] runs: usize,
+        #[case] verified: bool,
+    ) {
+        let message = create_secret_seeded(visible, secret, 7);
+        assert_eq!(hidden_spans(&message).len(), runs);
+        assert_eq!(extract_secret(&message).as_deref(), Some(secret));
+        assert_eq!(verify_placement(&message, 7), verified);
+    }
+
+    #[test]
+    fn test_placement_entropy() {
+        // 43 characters leave 42 positions; C(42, 1) = 42 layouts for one run.
+        assert!((placement_entropy(43, 1) - 42f64.log2()).abs() < 1e-9);
+        assert!((placement_entropy(6, 2) - 10f64.log2()).abs() < 1e-9);
+        assert_eq!(placement_entropy(43, 0), 0.0);
+        assert_eq!(placement_entropy(3, 2), 0.0);
+        assert_eq!(placement_entropy(0, 5), 0.0);
+
+        for len in 2..100 {
+            assert!(placement_entropy(len + 1, 4) >= placement_entropy(len, 4) - 1e-9);
+        }
+        // Past half of the positions, each extra run removes freedom.
+        for bytes in 21..42 {
+            assert!(placement_entropy(43, bytes + 1) < placement_entropy(43, bytes));
+        }
+    }
+
+    #[test]
+    fn test_extract_from_candidates() {
+        let first = create_secret("Hello, World!", "first");
+        let second = create_secret("Привет, мир!", "второй");
+        let broken = create_secret("Hello again", "lost").replacen(I_1, "", 1);
+        let candidates = ["plain text", &broken, &first, "", &second, &create_secret("Empty", "")];
+
+        assert_eq!(extract_first(&candidates), Some((2, "first".to_string())));
+        assert_eq!(extract_indexed(&candidates), vec![(2, "first".to_string()), (4, "второй".to_string())]);
+        assert_eq!(extract_first(&["plain", &broken]), None);
+        assert!(extract_indexed(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_payload_for_append() {
+        let existing = "Best regards, Alice";
+        let payload = payload_for_append(existing, "секрет").expect("Encoding failed");
+        assert_eq!(recover_carrier(&payload), "");
+        assert_eq!(extract_secret(&format!("{existing}{payload}")).as_deref(), Some("секрет"));
+
+        let conflicting = create_secret(existing, "other");
+        assert_eq!(payload_for_append(&conflicting, "секрет"), Err(EncodeError::CarrierContainsAlphabet));
+        assert_eq!(payload_for_append("", "секрет"), Err(EncodeError::EmptyCarrier));
+    }
+
+    #[rstest]
+    fn test_escaped_round_trip(
+        #[values(EscapeStyle::Rust, EscapeStyle::Json, EscapeStyle::Python)] style: EscapeStyle,
+        #[values("Hello, World!", "Say \"hi\"\n\\ Привет 🦀", "tag\u{E0041}\u{200B}")] carrier: &str,
+    ) {
+        let message = create_secret(carrier, "секрет");
+        let escaped = to_escaped(&message, style);
+        assert!(!escaped.chars().any(|c| is_invisible(c) || c.is_control()));
+
+        let parsed = from_escaped(&escaped, style).expect("Parsing failed");
+        assert_eq!(parsed, message);
+        assert_eq!(extract_secret(&parsed).as_deref(), Some("секрет"));
+    }
+
+    #[rstest]
+    #[case::rust(EscapeStyle::Rust, "\"a\\u{200c}\\u{e0041}\"")]
+    #[case::json(EscapeStyle::Json, "\"a\\u200c\\udb40\\udc41\"")]
+    #[case::python(EscapeStyle::Python, "\"a\\u200c\\U000e0041\"")]
+    fn test_escaped_format(#[case] style: EscapeStyle, #[case] expected: &str) {
+        assert_eq!(to_escaped("a\u{200C}\u{E0041}", style), expected);
+    }
+
+    #[rstest]
+    #[case::unquoted(EscapeStyle::Rust, "abc")]
+    #[case::inner_quote(EscapeStyle::Json, "\"a\"b\"")]
+    #[case::trailing_backslash(EscapeStyle::Python, "\"a\\\"")]
+    #[case::unknown_escape(EscapeStyle::Json, "\"\\q\"")]
+    #[case::lone_surrogate(EscapeStyle::Json, "\"\\ud800\"")]
+    #[case::rust_high_byte(EscapeStyle::Rust, "\"\\xff\"")]
+    #[case::python_surrogate(EscapeStyle::Python, "\"\\ud800\"")]
+    #[case::short_hex(EscapeStyle::Python, "\"\\u20\"")]
+    fn test_from_escaped_rejects(#[case] style: EscapeStyle, #[case] literal: &str) {
+        assert_eq!(from_escaped(literal, style), None);
+    }
+
+    #[rstest]
+    fn test_payload_entry_points_agree(
+        #[values("Hello, World!", "x", "Привет 🦀 мир", "")] carrier: &str,
+        #[values("", "secret", "секрет 🦀")] secret: &str,
+    ) {
+        let message = create_secret(carrier, secret);
+        let (visible, payload) = split_message(&message);
+        assert_eq!(recover_carrier(&message), carrier);
+        assert_eq!(visible, carrier);
+        assert_eq!(payload.as_bytes(), encode_only(secret).as_bytes());
+        if !carrier.is_empty() {
+            assert_eq!(payload_for_append(carrier, secret), Ok(encode_only(secret)));
+        }
+    }
+
+    #[test]
+    fn test_payload_bits() {
+        let message = create_secret("Hello, World!", "a");
+        // 'a' is 0x61, written least significant bit first.
+        let bits = [true, false, false, false, false, true, true, false];
+        assert_eq!(payload_bits(&message), bits);
+        assert_eq!(create_secret_from_bits("Hello, World!", &bits), message);
+        assert!(payload_bits("Hello").is_empty());
+
+        let mut flipped = bits;
+        flipped[1] = true;
+        assert_eq!(extract_secret(&create_secret_from_bits("Hello, World!", &flipped)).as_deref(), Some("c"));
+    }
+
+    #[test]
+    fn test_create_secret_after() {
+        let message = create_secret_after("Meet me at the старый bridge", "старый", "секрет").expect("Encoding failed");
+        assert_eq!(message, format!("Meet me at the старый{} bridge", encode("секрет")));
+        assert_eq!(extract_secret(&message).as_deref(), Some("секрет"));
+
+        let at_end = create_secret_after("Hello, World!", "World!", "hi").expect("Encoding failed");
+        assert_eq!(at_end, format!("Hello, World!{}", encode("hi")));
+        assert!(payload_at_edge(&at_end));
+
+        let first = create_secret_after("a b a", "a", "hi").expect("Encoding failed");
+        assert_eq!(hidden_spans(&first)[0].char_start, 1);
+        assert_eq!(create_secret_after("Hello, World!", "moon", "hi"), Err(EncodeError::NeedleNotFound));
+        assert_eq!(create_secret_after("", "moon", "hi"), Err(EncodeError::EmptyCarrier));
+        assert_eq!(create_secret_after("Hello, World!", "", "hi"), Err(EncodeError::EmptyNeedle));
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_extract_secret_gzip() {
+        use std::io::Write;
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all("секрет ".repeat(20).as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let message = format!("Hello,{} World!", encode_bytes(&compressed));
+        assert_eq!(extract_secret_gzip(&message), Some("секрет ".repeat(20)));
+        assert_eq!(extract_secret(&message), None);
+
+        // Plain secrets are unaffected.
+        let plain = create_secret("Hello, World!", "secret");
+        assert_eq!(extract_secret_gzip(&plain).as_deref(), Some("secret"));
+
+        // The magic alone isn't a gzip stream, and the raw bytes aren't UTF-8.
+        let truncated = format!("Hello,{} World!", encode_bytes(&[0x1F, 0x8B, 0x08]));
+        assert_eq!(extract_secret_gzip(&truncated), None);
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+        encoder.write_all(&vec![b'a'; MAX_SECRET_LEN + 1]).unwrap();
+        let bomb = format!("Hello,{} World!", encode_bytes(&encoder.finish().unwrap()));
+        assert_eq!(extract_secret_gzip(&bomb), None);
+    }
+
+    #[test]
+    fn test_assert_zero_width() {
+        assert_eq!(assert_zero_width(&create_secret("Hello, World!", "секрет")), Ok(()));
+        assert_eq!(assert_zero_width(&create_secret_with("Hello", "a", &Alphabet::SECONDARY).unwrap()), Ok(()));
+        assert_eq!(assert_zero_width("tabs\tand\nnewlines"), Ok(()));
+
+        let wide = Alphabet { zero: '\u{3164}', one: '\u{2800}' };
+        let message = format!("Hello{}", wide.encode("hi").collect::<String>());
+        assert_eq!(assert_zero_width(&message), Err(vec!['\u{3164}', '\u{2800}']));
+        assert_eq!(assert_zero_width("a\u{2009}b\u{2009}"), Err(vec!['\u{2009}']));
+    }
+
+    #[test]
+    fn test_create_secret_ascii() {
+        let message = create_secret_ascii("Hello, World!", "секрет").expect("Encoding failed");
+        assert!(recover_carrier(&message).is_ascii());
+        assert_eq!(extract_secret(&message).as_deref(), Some("секрет"));
+
+        assert_eq!(create_secret_ascii("Привет, мир!", "secret"), Err(EncodeError::NonAsciiCarrier('П')));
+        assert_eq!(create_secret_ascii("Hi \u{200B}", "secret"), Err(EncodeError::NonAsciiCarrier('\u{200B}')));
+        assert_eq!(create_secret_ascii("", "secret"), Err(EncodeError::EmptyCarrier));
+    }
+
+    #[cfg(feature = "transliterate")]
+    #[test]
+    fn test_create_secret_transliterated() {
+        let message = create_secret_transliterated("Привет, мир!", "секрет").expect("Encoding failed");
+        assert_eq!(recover_carrier(&message), "Privet, mir!");
+        assert_eq!(extract_secret(&message).as_deref(), Some("секрет"));
+        assert_eq!(create_secret_transliterated("Hello", "a"), create_secret_ascii("Hello", "a"));
+    }
+
+    #[test]
+    fn test_extract_with_metrics() {
+        let message = format!("\u{FEFF}{}\u{200B}\u{2060}", create_secret("Hello, World!", "секрет"));
+        let (secret, metrics) = extract_with_metrics(&message);
+        assert_eq!(secret.as_deref(), Some("секрет"));
+        assert_eq!(metrics, ExtractMetrics { alphabet_chars: 96, skipped_zero_width: 3, payload_bytes: 12 });
+
+        let broken = message.replacen(I_0, "", 1);
+        let (secret, metrics) = extract_with_metrics(&broken);
+        assert_eq!(secret, None);
+        assert_eq!(metrics, ExtractMetrics { alphabet_chars: 95, skipped_zero_width: 3, payload_bytes: 11 });
+        assert_eq!(extract_with_metrics("plain"), (Some(String::new()), ExtractMetrics::default()));
+    }
+
+    #[test]
+    fn test_metadata_round_trip() {
+        let meta = [("author", "Алиса"), ("content-type", "text/plain")];
+        let message = create_secret_meta("Hello, World!", "секрет", &meta).expect("Metadata too large");
+        assert_eq!(recover_carrier(&message), "Hello, World!");
+
+        let (secret, recovered) = extract_meta(&message).expect("No metadata");
+        assert_eq!(secret, "секрет");
+        assert_eq!(recovered, meta.map(|(key, value)| (key.to_string(), value.to_string())));
+        // Plain readers see no text secret.
+        assert_eq!(extract_secret(&message), None);
+
+        let empty = create_secret_meta("Hello", "", &[]).expect("Metadata too large");
+        assert_eq!(extract_meta(&empty), Some((String::new(), Vec::new())));
+    }
+
+    #[test]
+    fn test_metadata_rejects() {
+        let long_key = "k".repeat(256);
+        let long_value = "v".repeat(65536);
+        assert_eq!(create_secret_meta("Hello", "s", &[(&long_key, "v")]), None);
+        assert_eq!(create_secret_meta("Hello", "s", &[("k", &long_value)]), None);
+
+        assert_eq!(extract_meta(&create_secret("Hello", "plain")), None);
+        let message = create_secret_meta("Hello", "s", &[("key", "value")]).expect("Metadata too large");
+        let payload = decode_bytes(&remove_unnecessary_symbols(&message)).unwrap();
+        let truncated = format!("Hello{}", encode_bytes(&payload[..6]));
+        assert_eq!(extract_meta(&truncated), None);
+    }
+
+    #[test]
+    fn test_position_report() {
+        let visible = "The quick brown fox jumps over the lazy dog, then the cat follows it home";
+        let middle = position_report(&create_secret(visible, "hi"));
+        assert_eq!(middle, PositionReport { leading: false, trailing: false, clustering: 1.0 });
+
+        let spread = position_report(&create_secret_spread(visible, "hi", SpreadDensity::Even));
+        assert!(!spread.leading && !spread.trailing);
+        assert_eq!(spread.clustering, 1.0 / 16.0);
+
+        let edges = position_report(&format!("{}{visible}{}", encode("a"), encode("b")));
+        assert_eq!(edges, PositionReport { leading: true, trailing: true, clustering: 0.5 });
+        assert_eq!(position_report(visible), PositionReport { leading: false, trailing: false, clustering: 0.0 });
+    }
+
+    #[test]
+    fn test_visualize_char() {
+        let bits = |pattern: &str| pattern.chars().map(|bit| bit == '1').collect::<Vec<_>>();
+        // 'A' is 0x41.
+        assert_eq!(visualize_char('A'), bits("10000010"));
+        // 'ж' is 0xD0 0xB6.
+        assert_eq!(visualize_char('ж'), bits("0000101101101101"));
+        assert_eq!(visualize_char('🦀').len(), 32);
+    }
+
+    #[test]
+    fn test_footer_detects_truncation() {
+        let message = create_secret_with_footer("Hello, World!", "секрет");
+        assert_eq!(extract_secret_with_footer(&message).as_deref(), Ok("секрет"));
+
+        // A clipboard manager cuts the message off inside the footer.
+        let spans = hidden_spans(&message);
+        let cut = &message[..spans[0].byte_end - 3 * EXP_SIZE];
+        assert_eq!(
+            extract_secret_with_footer(cut),
+            Err(FooterError::TruncatedMessage { expected: 13, available: 12 })
+        );
+        // Cut right after the secret, so the footer is gone entirely.
+        let cut = &message[..spans[0].byte_end - 8 * EXP_SIZE];
+        assert_eq!(
+            extract_secret_with_footer(cut),
+            Err(FooterError::TruncatedMessage { expected: 13, available: 12 })
+        );
+        let cut = &message[..spans[0].byte_start + 100 * EXP_SIZE];
+        assert!(matches!(extract_secret_with_footer(cut), Err(FooterError::TruncatedMessage { .. })));
+
+        assert_eq!(extract_secret_with_footer(&create_secret("Hello", "plain")), Err(FooterError::NotFramed));
+        assert_eq!(extract_secret_with_footer("Hello"), Err(FooterError::NotFramed));
+
+        let extra = format!("{message}{}", encode("!"));
+        assert_eq!(
+            extract_secret_with_footer(&extra),
+            Err(FooterError::TrailingData { expected: 13, available: 14 })
+        );
+    }
+
+    #[rstest]
+    #[case(0, 1)]
+    #[case(127, 1)]
+    #[case(128, 2)]
+    #[case(16383, 2)]
+    #[case(16384, 3)]
+    fn test_varint_boundaries(#[case] len: usize, #[case] header: usize) {
+        let mut bytes = Vec::new();
+        push_varint(&mut bytes, len);
+        assert_eq!(bytes.len(), header);
+        bytes.push(0xAB);
+        assert_eq!(take_varint(&bytes), Some((len, &[0xAB][..])));
+        assert_eq!(take_varint(&bytes[..header - 1]), None);
+
+        let secret = "x".repeat(len);
+        let framed = create_secret_with_footer("Hello, World!", &secret);
+        assert_eq!(extract_raw(&framed).map(|payload| payload.len()), Some(1 + header + len + 1));
+        assert_eq!(extract_secret_with_footer(&framed), Ok(secret.clone()));
+
+        let target = 13 + (header + len) * 8 + 20;
+        let fixed = create_secret_fixed("Hello, World!", &secret, Some(target)).expect("Message too long");
+        assert_eq!(fixed.chars().count(), target);
+        assert_eq!(extract_secret_fixed(&fixed), Some(secret));
+    }
+
+    #[test]
+    fn test_varint_rejects_overflow() {
+        assert_eq!(take_varint(&[0xFF, 0xFF, 0xFF, 0xFF, 0x0F]), Some((u32::MAX as usize, &[][..])));
+        assert_eq!(take_varint(&[0xFF, 0xFF, 0xFF, 0xFF, 0x1F]), None);
+        assert_eq!(take_varint(&[0x80; 6]), None);
+    }
+
+    #[test]
+    fn test_fixed_total_hides_length() {
+        let short = create_secret_fixed("Hello, World!", "hi", Some(200)).expect("Message too long");
+        let long = create_secret_fixed("Hello, World!", "a longer secret", Some(200)).expect("Message too long");
+        assert_eq!(short.chars().count(), 200);
+        assert_eq!(long.chars().count(), 200);
+        assert_eq!(extract_secret_fixed(&short).as_deref(), Some("hi"));
+        assert_eq!(extract_secret_fixed(&long).as_deref(), Some("a longer secret"));
+        assert_eq!(recover_carrier(&short).trim_end(), "Hello, World!");
+
+        let nuls = create_secret_fixed("Hello, World!", "\0\0hello\0", Some(200)).expect("Message too long");
+        assert_eq!(extract_secret_fixed(&nuls).as_deref(), Some("\0\0hello\0"));
+
+        let empty = create_secret_fixed("Hello, World!", "", Some(200)).expect("Message too long");
+        assert_eq!(empty.chars().count(), 200);
+        assert_eq!(extract_secret_fixed(&empty).as_deref(), Some(""));
+
+        assert_eq!(
+            create_secret_fixed("Hello, World!", "a longer secret", Some(100)),
+            Err(EncodeError::ExceedsFixedTotal { needed: 149, target: 100 })
+        );
+        let unpadded = create_secret_fixed("Hello, World!", "secret", None).expect("Message too long");
+        assert_eq!(extract_secret_fixed(&unpadded).as_deref(), Some("secret"));
+        assert_eq!(payload_bits(&unpadded).len(), 8 * 8);
+        assert_eq!(extract_secret_fixed(&create_secret("Hello, World!", "\u{6}secret")), None);
+    }
+
+    #[test]
+    fn test_script_keeps_devanagari_clusters() {
+        let carrier = "नमस्ते दुनिया";
+        let message = create_secret_for_script(carrier, "секрет", Script::Indic).expect("No safe position");
+        assert_eq!(extract_secret(&message).as_deref(), Some("секрет"));
+        assert_eq!(recover_carrier(&message), carrier);
+
+        // Each grapheme of the carrier is still intact, so the payload didn't
+        // split off a vowel sign (e.g. े) or a virama (्) from its consonant.
+        let span = hidden_spans(&message)[0];
+        assert_eq!(message[..span.byte_start].chars().next_back(), Some(' '));
+        assert_eq!(message[span.byte_end..].chars().next(), Some('द'));
+
+        // Without a hint, the middle of the string falls inside a word.
+        let naive = create_secret(carrier, "секрет");
+        let span = hidden_spans(&naive)[0];
+        assert_ne!(naive[..span.byte_start].chars().next_back(), Some(' '));
+
+        let single_word = create_secret_for_script("नमस्ते", "hi", Script::Indic).unwrap();
+        assert!(single_word.starts_with("नमस्ते"));
+        assert_eq!(extract_secret(&single_word).as_deref(), Some("hi"));
+    }
+
+    #[test]
+    fn test_script_joining_needs_non_letters() {
+        let carrier = "مرحبا، كيف حالك؟";
+        let message = create_secret_for_script(carrier, "hi", Script::Joining).expect("No safe position");
+        assert_eq!(extract_secret(&message).as_deref(), Some("hi"));
+        let span = hidden_spans(&message)[0];
+        assert_eq!(message[..span.byte_start].chars().next_back(), Some('،'));
+        assert_eq!(message[span.byte_end..].chars().next(), Some(' '));
+
+        assert_eq!(create_secret_for_script("مرحبا كيف", "hi", Script::Joining), None);
+        assert_eq!(create_secret_for_script("Hello", "hi", Script::Default), Some(create_secret("Hello", "hi")));
+    }
+
+    #[test]
+    fn test_noise_varies_invisible_count() {
+        let invisible = |message: &str| message.chars().filter(|&c| c == NOISE_CHAR || Alphabet::DEFAULT.contains(c)).count();
+        let messages: Vec<String> =
+            (0..4).map(|seed| create_secret_noisy("Hello, World!", "секрет", NoiseLevel::High, seed)).collect();
+        let counts: BTreeSet<usize> = messages.iter().map(|m| invisible(m)).collect();
+        assert!(counts.len() > 1, "counts {counts:?}");
+        for message in &messages {
+            assert_eq!(extract_secret(message).as_deref(), Some("секрет"));
+            assert_eq!(recover_carrier(&message.replace(NOISE_CHAR, "")), "Hello, World!");
+            assert!(invisible(message) <= "секрет".len() * 16);
+        }
+
+        assert_eq!(create_secret_noisy("Hello", "hi", NoiseLevel::None, 7), create_secret("Hello", "hi"));
+        assert_eq!(create_secret_noisy("Hello", "hi", NoiseLevel::Low, 7), create_secret_noisy("Hello", "hi", NoiseLevel::Low, 7));
+    }
+
+    #[test]
+    fn test_leading_nul_bytes_survive() {
+        let secret = "\0\0hello";
+        assert_eq!(extract_secret(&create_secret("Hello, World!", secret)).as_deref(), Some(secret));
+        assert_eq!(try_extract_secret(&create_secret("Hello, World!", secret)).as_deref(), Ok(secret));
+        assert_eq!(extract_raw(&create_secret("ab", secret)), Some(b"\0\0hello".to_vec()));
+
+        let framed = create_secret_with_footer("Hello, World!", secret);
+        assert_eq!(extract_secret_with_footer(&framed).as_deref(), Ok(secret));
+        let meta = create_secret_meta("Hello, World!", secret, &[("k", "\0")]).unwrap();
+        assert_eq!(extract_meta(&meta), Some((secret.to_string(), vec![("k".to_string(), "\0".to_string())])));
+    }
+
+    #[test]
+    fn test_public_api_never_panics() {
+        let crafted = [
+            encode_bytes(&[LENGTH_MARKER, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]),
+            encode_bytes(&[LENGTH_MARKER, 0xFF, 0xFF, 0xFF, 0xFF, 0x0F]),
+            encode_bytes(&[LENGTH_MARKER, 0x05, b'a']),
+            encode_bytes(&[META_MARKER, 0xFF, 0xFF, b'k']),
+            encode_bytes(&[META_MARKER, 0x01, 0x01, b'k', 0xFF, 0xFF]),
+            encode_bytes(&[0x1F, 0x8B, 0x08, 0x00]),
+            encode_bytes(&[0xFF; 12]),
+            encode_bytes(&[0x00; 3]),
+            format!("🦀{}ж{}", I_1.repeat(7), I_0.repeat(9)),
+        ];
+        let inputs: Vec<&str> = [
+            "", " ", "🦀", "ж", "\u{200D}", "\u{200C}\u{200D}", "👨‍👩‍👧", "\0", "e\u{301}", "नमस्ते", "مرحبا",
+            "&", "&#", "&#x;", "&#xFFFFFFFF;", "&#1114112;", "<", "<!--", "<!-- -->", "<p", "%", "%E2", "%E2%80%8C%",
+            "%ZZ", "\"", "\"\\u{", "\"\\u{110000}\"", "\"\\ud800\"", "\"\\U0011FFFF\"", "\\",
+        ]
+        .into_iter()
+        .chain(crafted.iter().map(String::as_str))
+        .collect();
+
+        for &a in &inputs {
+            let _ = (extract_secret(a), try_extract_secret(a), extract_raw(a), extract_partial(a));
+            let _ = (extract_secret_fixed(a), extract_secret_with_footer(a), extract_meta(a));
+            let _ = (extract_secret_redundant(a), extract_all(a), extract_with_metrics(a));
+            let _ = (extract_secret_from_html(a), extract_secret_from_url(a), decode_entities(a));
+            let _ = (from_escaped(a, EscapeStyle::Rust), from_escaped(a, EscapeStyle::Json), from_escaped(a, EscapeStyle::Python));
+            let _ = (to_escaped(a, EscapeStyle::Json), recover_carrier(a), split_message(a), canonicalize(a));
+            let _ = (detectability(a), position_report(a), payload_at_edge(a), assert_zero_width(a), verify_beacon(a));
+            let _ = (verify_token(a, "0"), verify_placement(a, 0), payload_bits(a), redact_payload(a, "x"));
+            let _ = grapheme_count(a);
+            #[cfg(feature = "gzip")]
+            let _ = extract_secret_gzip(a);
+            let _ = extract_secret_transformed(a, |bytes| bytes.to_vec());
+
+            for &b in &inputs {
+                let _ = (create_secret(a, b), create_secret_checked(a, b, true), create_secret_with_footer(a, b));
+                let _ = (create_secret_meta(a, b, &[(b, a)]), create_secret_redundant(a, b, 0));
+                let _ = (create_secret_redundant(a, b, 3), create_secret_after(a, b, b), create_secret_templated(a, b, b));
+                let _ = (create_secret_emoji(a, b), create_secret_capped(a, b, 0), create_secret_capped(a, b, usize::MAX));
+                let _ = (create_secret_spread(a, b, SpreadDensity::Even), create_secret_spread(a, b, SpreadDensity::FixedGap(usize::MAX)));
+                let _ = (create_secret_seeded(a, b, u64::MAX), create_secret_noisy(a, b, NoiseLevel::High, 0));
+                let _ = (create_secret_fixed(a, b, Some(0)), create_secret_fixed(a, b, Some(usize::MAX)));
+                let _ = (create_secret_for_script(a, b, Script::Indic), create_secret_for_script(a, b, Script::Joining));
+                let _ = (create_secret_ascii(a, b), create_secret_guarded(a, b, EmptyCarrier::UseDefault), payload_for_append(a, b));
+                let _ = (create_secret_html_comment(a, b), create_secret_with_token(a, b), rewrap(a, b), same_secret(a, b));
+                let _ = create_secret_dual(a, (&Alphabet::DEFAULT, b), (&Alphabet::SECONDARY, a));
+                let _ = (extract_first(&[a, b]), extract_indexed(&[a, b]), create_secret_batch(a, &[a, b]));
+            }
+        }
+        let _ = (placement_entropy(0, 0), placement_entropy(usize::MAX, usize::MAX), placement_entropy(1, usize::MAX));
+        let _ = (min_carrier_len("a", f64::NAN), min_carrier_len("a", f64::INFINITY), min_carrier_len("a", f64::MIN_POSITIVE));
+        let _ = (create_secret_from_bits("a", &[true; 7]), visualize_char(char::MAX), Alphabet::new('\u{200C}', '\u{200C}'));
+    }
+
+    #[test]
+    fn test_create_secret_cow() {
+        assert!(matches!(create_secret_cow("Hello, World!", ""), Cow::Borrowed("Hello, World!")));
+        let message = create_secret_cow("Hello, World!", "секрет");
+        assert!(matches!(message, Cow::Owned(_)));
+        assert_eq!(message, create_secret("Hello, World!", "секрет"));
+    }
+
+    #[test]
+    fn test_create_secret_batch() {
+        let visible = "Это тест, а вот и продолжение";
+        let secrets = ["alice", "bob", "", "карл 🦀"];
+        let messages = create_secret_batch(visible, &secrets);
+
+        assert_eq!(messages.len(), secrets.len());
+        for (message, secret) in messages.iter().zip(secrets) {
+            assert_eq!(*message, create_secret(visible, secret));
+            assert_eq!(extract_secret(message).as_deref(), Some(secret));
+            assert_eq!(recover_carrier(message), visible);
+        }
+        assert!(create_secret_batch(visible, &[]).is_empty());
+    }
+
+    #[rstest]
+    #[case::fits("da", 2, "da", false)]
+    #[case::exact("дa", 3, "дa", false)]
+    #[case::mid_char("дa🦀", 5, "дa", true)]
+    #[case::inside_first_char("д", 1, "", true)]
+    #[case::zero("abc", 0, "", true)]
+    fn test_create_secret_capped(
+        #[case] secret: &str,
+        #[case] max_bytes: usize,
+        #[case] expected: &str,
+        #[case] truncated: bool,
+    ) {
+        let (message, was_truncated) = create_secret_capped("Hello, World!", secret, max_bytes);
+        assert_eq!(was_truncated, truncated);
+        assert_eq!(extract_secret(&message).as_deref(), Some(expected));
+        assert!(expected.len() <= max_bytes);
+    }
+
+    #[test]
+    fn test_extract_partial_complete() {
+        let message = create_secret("Hello, World!", "суперsecret");
+        let result = extract_partial(&message);
+        assert_eq!(result, PartialResult { prefix: "суперsecret".to_string(), truncated: false, lost_bits: 0 });
+    }
+
+    #[test]
+    fn test_extract_partial_truncated() {
+        let secret = "aбв🦀z";
+        let payload = encode(secret);
+        let payload_chars = payload.len() / EXP_SIZE;
+
+        for kept in 0..=payload_chars {
+            let message = format!("Cut off here: {}", &payload[..kept * EXP_SIZE]);
+            let result = extract_partial(&message);
+
+            // The longest whole-character prefix of the complete bytes.
+            let mut expected_len = kept / 8;
+            while !secret.is_char_boundary(expected_len) {
+                expected_len -= 1;
+            }
+            assert_eq!(result.prefix, secret[..expected_len], "kept {kept} bits");
+            assert_eq!(result.lost_bits, kept - expected_len * 8);
+            assert_eq!(result.truncated, result.lost_bits > 0);
+        }
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_beacon_intact() {
+        let visible = "This contract is valid until the end of the year.";
+        let message = embed_beacon(visible);
+
+        assert_eq!(recover_carrier(&message), visible);
+        assert_eq!(verify_beacon(&message), BeaconStatus::Intact);
+        // A beacon is not a text secret.
+        assert_eq!(extract_secret(&message), None);
+    }
+
+    #[test]
+    fn test_beacon_modified() {
+        let message = embed_beacon("This contract is valid until the end of the year.");
+        let edited = message.replace("year", "decade");
+        assert_eq!(verify_beacon(&edited), BeaconStatus::Modified);
+    }
+
+    #[test]
+    fn test_no_beacon() {
+        assert_eq!(verify_beacon("Plain text"), BeaconStatus::NoBeacon);
+        assert_eq!(verify_beacon(&create_secret("Hello", "secret")), BeaconStatus::NoBeacon);
+    }
+
+    #[rstest]
+    fn test_all_combinations(
+        #[values(
+            "Hello", "secret123", "A quick brown fox.", "!@#$%^&*()_+",
+            "你好世界", "Rustacean 🦀", "Привет", "δοκιμή",
+            "", " ", "\n\t",
+            "a-very-long-string-that-tests-buffer-handling-and-other-edge-cases",
+            "another-similarly-long-string-to-ensure-no-static-assumptions-are-made",
+            "Text with 123 and some Cyrillic: Привет", "你好 with some emoji 😃 and numbers 456",
+            "json-like:{'key':'value'}", "<html><body>test</body></html>", "a final test string"
+        )] 
+        base: &str,
+        #[values(
+            "Hello", "secret123", "A quick brown fox.", "!@#$%^&*()_+",
+            "你好世界", "Rustacean 🦀", "Привет", "δοκιμή",
+            "", " ", "\n\t",
+            "a-very-long-string-that-tests-buffer-handling-and-other-edge-cases",
+            "another-similarly-long-string-to-ensure-no-static-assumptions-are-made",
+            "Text with 123 and some Cyrillic: Привет", "你好 with some emoji 😃 and numbers 456",
+            "json-like:{'key':'value'}", "<html><body>test</body></html>", "a final test string"
+        )] secret: &str
+    ) {
+        // The test logic remains the same
+        let message = create_secret(base, secret);
+        let extracted = extract_secret(&message).expect("Extraction failed");
+        assert_eq!(secret, extracted);
+    }
+
+
+}
+
+// Tests for the wasm32 target, run with `wasm-pack test --node`.
+#[cfg(all(test, target_arch = "wasm32"))]
+mod wasm_tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    #[wasm_bindgen_test]
+    fn test_round_trip() {
+        for secret in ["", "secret", "секрет", "🦀 crab"] {
+            let message = create_secret("Hello, World!", secret);
+            assert_eq!(extract_secret(&message).as_deref(), Some(secret));
+            assert_eq!(recover_carrier(&message), "Hello, World!");
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn test_checked_round_trip() {
+        let message = create_secret_checked("Hello, World!", "secret", true).expect("Encoding failed");
+        assert_eq!(extract_secret(&message).as_deref(), Some("secret"));
+        assert_eq!(create_secret_checked("Hello, World!", &"a".repeat(MAX_SECRET_LEN + 1), false).err(),
+            Some(EncodeError::PayloadTooLarge { len: MAX_SECRET_LEN + 1, max: MAX_SECRET_LEN }));
+    }
+
+    #[wasm_bindgen_test]
+    fn test_spans_and_counts() {
+        let message = create_secret("Привет, мир!", "hi");
+        let spans = hidden_spans(&message);
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].char_end - spans[0].char_start, 16);
+        assert_eq!(scalar_count(&message), 12 + 16);
+    }
 }
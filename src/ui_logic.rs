@@ -0,0 +1,241 @@
+//! Pure logic behind the Hide and Seek pages, kept apart from the components so
+//! it can be tested without rendering.
+
+use evaporate::text_removal::{self, Alphabet, ExtractError};
+
+/// Computes the combined message shown on the Hide page.
+pub fn hide_output(visible: &str, hidden: &str) -> Result<String, text_removal::EncodeError> {
+    text_removal::create_secret_checked(visible, hidden, false)
+}
+
+/// Explains an encoding failure to the user.
+pub fn encode_error_message(error: &text_removal::EncodeError) -> String {
+    use text_removal::EncodeError;
+    match error {
+        EncodeError::EmptyCarrier => "Enter some visible text to hide the secret in.".to_string(),
+        EncodeError::CarrierContainsAlphabet => {
+            "The visible text already contains hidden characters. Paste plain text instead.".to_string()
+        }
+        EncodeError::PayloadTooLarge { max, .. } => {
+            format!("The hidden text is too long. It can be at most {max} bytes.")
+        }
+        EncodeError::RoundTripFailed => "Something went wrong: the result could not be read back.".to_string(),
+        EncodeError::AlphabetInvalid(error) => format!("The chosen hidden characters can't be used: {error}."),
+        EncodeError::NeedleNotFound => "The visible text doesn't contain the word to hide the text after.".to_string(),
+        EncodeError::EmptyNeedle => "Enter the word to hide the text after.".to_string(),
+        EncodeError::NonAsciiCarrier(c) => format!("The visible text may only use ASCII characters, but contains {c:?}."),
+        EncodeError::ExceedsFixedTotal { target, .. } => {
+            format!("The message doesn't fit in {target} characters. Shorten the visible or hidden text.")
+        }
+    }
+}
+
+/// Returns `true` if the secret can still be extracted from an edited output.
+pub fn payload_survived(edited: &str, hidden: &str) -> bool {
+    text_removal::extract_secret(edited).as_deref() == Some(hidden)
+}
+
+/// How the Seek page interprets the combined message.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputFormat {
+    Plain,
+    Html,
+}
+
+/// Cleans up pasted plain text before extraction. Some apps copy zero-width
+/// characters as HTML character references (`&zwnj;`), text copied from an
+/// address bar may be percent-encoded, and surrounding whitespace is trimmed.
+pub fn normalize_pasted(combined: &str) -> String {
+    text_removal::decode_url_escapes(&text_removal::decode_entities(combined)).trim().to_string()
+}
+
+/// Extracts the secret of a combined message read in the given format. Plain
+/// text is first read as `create_secret_fixed` output, and text without a
+/// zero-width payload is also checked for tag characters, which survive copying
+/// out of PDFs.
+pub fn recovered_secret(combined: &str, format: InputFormat) -> Result<String, ExtractError> {
+    match format {
+        InputFormat::Plain => {
+            let pasted = normalize_pasted(combined);
+            if let Some(secret) = text_removal::extract_secret_fixed(&pasted) {
+                return Ok(secret);
+            }
+            match text_removal::try_extract_secret(&pasted) {
+                Err(ExtractError::NoPayload) => text_removal::try_extract_secret_with(&pasted, &Alphabet::TAGS),
+                result => result,
+            }
+        }
+        InputFormat::Html => text_removal::try_extract_secret_from_html(combined),
+    }
+}
+
+/// What the Seek page found in the combined message.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SeekState {
+    /// There is no hidden data at all.
+    NotFound,
+    /// The hidden data decodes to an empty message: a fixed-length message
+    /// with an empty secret.
+    Empty,
+    /// The hidden message.
+    Found(String),
+    /// There is hidden data, but it can't be decoded.
+    Corrupted(ExtractError),
+}
+
+/// Classifies the combined message read in the given format.
+pub fn seek_state(combined: &str, format: InputFormat) -> SeekState {
+    match recovered_secret(combined, format) {
+        Ok(secret) if secret.is_empty() => SeekState::Empty,
+        Ok(secret) => SeekState::Found(secret),
+        Err(ExtractError::NoPayload) => SeekState::NotFound,
+        Err(error) => SeekState::Corrupted(error),
+    }
+}
+
+/// Computes the text shown on the Seek page for the given combined message.
+pub fn seek_output(combined: &str, format: InputFormat) -> String {
+    match seek_state(combined, format) {
+        SeekState::NotFound => "No hidden message.".to_string(),
+        SeekState::Empty => "Hidden message is empty.".to_string(),
+        SeekState::Found(secret) => secret,
+        SeekState::Corrupted(error) => format!("Found hidden data but it's corrupted: {error}."),
+    }
+}
+
+/// Describes where each run of payload characters sits in `message`, with
+/// 1-based character positions, for the Seek page.
+pub fn span_descriptions(message: &str) -> Vec<String> {
+    let total = message.chars().count();
+    text_removal::hidden_spans(message)
+        .iter()
+        .map(|span| {
+            let place = match (span.char_start, span.char_end) {
+                (0, _) => "start",
+                (_, end) if end == total => "end",
+                _ => "middle",
+            };
+            format!("Hidden data found at characters {}–{} ({place})", span.char_start + 1, span.char_end)
+        })
+        .collect()
+}
+
+/// Decides how to read a file dropped onto the Seek page, based on its name and,
+/// for unknown extensions, its contents.
+pub fn detect_format(name: &str, contents: &str) -> Result<InputFormat, String> {
+    let extension = name.rsplit_once('.').map(|(_, ext)| ext.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("html" | "htm") => Ok(InputFormat::Html),
+        Some("txt") => Ok(InputFormat::Plain),
+        None if contents.trim_start().starts_with('<') => Ok(InputFormat::Html),
+        None => Ok(InputFormat::Plain),
+        Some(_) => Err(format!("{name} is not a .txt or .html file.")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hide_output() {
+        let output = hide_output("Hello, World!", "Hidden text").expect("Encoding failed");
+        assert_eq!(output, text_removal::create_secret("Hello, World!", "Hidden text"));
+        assert_eq!(text_removal::extract_secret(&output).as_deref(), Some("Hidden text"));
+
+        let error = hide_output(&output, "Another").expect_err("Carrier already has a payload");
+        assert!(encode_error_message(&error).contains("already contains hidden characters"));
+    }
+
+    #[test]
+    fn test_payload_survived() {
+        let output = hide_output("Hello, World!", "Hidden text").unwrap();
+        assert!(payload_survived(&format!("Dear Bob, {output}"), "Hidden text"));
+        assert!(!payload_survived(&output.replacen('\u{200D}', "", 1), "Hidden text"));
+        assert!(!payload_survived("Hello, World!", "Hidden text"));
+    }
+
+    #[test]
+    fn test_seek_output() {
+        let combined = text_removal::create_secret("Hello, World!", "Hidden text");
+        assert_eq!(seek_output(&combined, InputFormat::Plain), "Hidden text");
+
+        // A payload with a missing character can't be decoded.
+        let truncated = combined.replacen('\u{200C}', "", 1);
+        assert_eq!(
+            seek_output(&truncated, InputFormat::Plain),
+            "Found hidden data but it's corrupted: the payload ends 7 bits into a byte."
+        );
+        assert_eq!(seek_output("Hello, World!", InputFormat::Plain), "No hidden message.");
+
+        let html = format!("<p>{combined}</p>");
+        assert_eq!(seek_output(&html, InputFormat::Html), "Hidden text");
+    }
+
+    #[test]
+    fn test_seek_state() {
+        let combined = text_removal::create_secret("Hello, World!", "Hidden text");
+        assert_eq!(seek_state(&combined, InputFormat::Plain), SeekState::Found("Hidden text".to_string()));
+        assert_eq!(seek_state("Hello, World!", InputFormat::Plain), SeekState::NotFound);
+        assert_eq!(seek_state(&text_removal::create_secret("Hello", ""), InputFormat::Plain), SeekState::NotFound);
+
+        let padded = text_removal::create_secret_fixed("Hello", "", Some(40)).unwrap();
+        assert_eq!(seek_state(&padded, InputFormat::Plain), SeekState::Empty);
+        assert_eq!(seek_output(&padded, InputFormat::Plain), "Hidden message is empty.");
+        let fixed = text_removal::create_secret_fixed("Hello", "hi", Some(80)).unwrap();
+        assert_eq!(seek_state(&fixed, InputFormat::Plain), SeekState::Found("hi".to_string()));
+
+        let nul = text_removal::create_secret("Hello", "\0");
+        assert_eq!(seek_state(&nul, InputFormat::Plain), SeekState::Corrupted(ExtractError::OnlyNulBytes));
+
+        let corrupted = format!("<p>{}</p>", combined.replacen('\u{200D}', "", 2));
+        assert_eq!(
+            seek_state(&corrupted, InputFormat::Html),
+            SeekState::Corrupted(ExtractError::IncompleteByte { bits: 6 })
+        );
+    }
+
+    #[test]
+    fn test_span_descriptions() {
+        let combined = text_removal::create_secret("Hello, World!", "a");
+        assert_eq!(span_descriptions(&combined), ["Hidden data found at characters 7–14 (middle)"]);
+
+        let spread = format!("{}x{}", text_removal::encode_only("a"), text_removal::encode_only("b"));
+        assert_eq!(
+            span_descriptions(&spread),
+            ["Hidden data found at characters 1–8 (start)", "Hidden data found at characters 10–17 (end)"]
+        );
+        assert!(span_descriptions("Hello").is_empty());
+    }
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(detect_format("message.txt", "<p>hi</p>"), Ok(InputFormat::Plain));
+        assert_eq!(detect_format("page.HTML", "hi"), Ok(InputFormat::Html));
+        assert_eq!(detect_format("page.htm", "hi"), Ok(InputFormat::Html));
+        assert_eq!(detect_format("clipboard", "  <div>hi</div>"), Ok(InputFormat::Html));
+        assert_eq!(detect_format("clipboard", "hi"), Ok(InputFormat::Plain));
+        assert!(detect_format("image.png", "").is_err());
+    }
+
+    #[test]
+    fn test_seek_decodes_pasted_entities() {
+        let message = text_removal::create_secret("Hello, World!", "secret");
+        let pasted = format!("  {}\n", message.replace('\u{200C}', "&zwnj;").replace('\u{200D}', "&#x200D;"));
+        assert_eq!(normalize_pasted(&pasted), message);
+        assert_eq!(seek_output(&pasted, InputFormat::Plain), "secret");
+        assert_eq!(normalize_pasted("  Tom &amp Jerry  "), "Tom &amp Jerry");
+    }
+
+    #[test]
+    fn test_recovered_secret() {
+        let message = text_removal::create_secret("Hello, World!", "secret");
+        assert_eq!(recovered_secret(&message, InputFormat::Plain).as_deref(), Ok("secret"));
+        assert_eq!(recovered_secret(&format!("<p>{message}</p>"), InputFormat::Html).as_deref(), Ok("secret"));
+        assert_eq!(recovered_secret("Hello", InputFormat::Plain), Err(ExtractError::NoPayload));
+        let tagged = text_removal::create_secret_with("Hello, World!", "secret", &Alphabet::TAGS).unwrap();
+        assert_eq!(recovered_secret(&tagged, InputFormat::Plain).as_deref(), Ok("secret"));
+        let url = message.replace('\u{200C}', "%E2%80%8C").replace('\u{200D}', "%E2%80%8D");
+        assert_eq!(recovered_secret(&url, InputFormat::Plain).as_deref(), Ok("secret"));
+    }
+}